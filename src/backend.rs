@@ -0,0 +1,229 @@
+//! Storage of the deadlines of a pool's timer driven messages.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::Duration;
+
+use smallvec::SmallVec;
+
+use crate::MessageHandle;
+
+/// Deadlines kept unordered inline before switching to a heap.
+const INLINE: usize = 2;
+
+/// How a [`MessagePool`](crate::MessagePool) keeps track of when its timer driven messages are due.
+///
+/// Both backends write the same messages at the same time, they only differ in cost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PoolBackend {
+    /// Binary heap ordered by deadline. Scheduling and writing cost `O(log n)` in the pending messages.
+    #[default]
+    Heap,
+    /// Hierarchical timer wheel with slots `resolution` wide. Scheduling is `O(1)` and each run only
+    /// touches the slots it passes, so pools with tens of thousands of pending messages stay cheap.
+    ///
+    /// The resolution only buckets deadlines, messages are still written exactly when due.
+    TimerWheel { resolution: Duration },
+}
+
+pub(crate) enum Deadlines {
+    /// [`PoolBackend::Heap`] while there are only a few deadlines.
+    Inline(SmallVec<[(Duration, MessageHandle); INLINE]>),
+    Heap(BinaryHeap<Reverse<(Duration, MessageHandle)>>),
+    Wheel(Box<TimerWheel>),
+}
+
+impl Default for Deadlines {
+    fn default() -> Self {
+        Deadlines::Inline(SmallVec::new())
+    }
+}
+
+impl Deadlines {
+    pub(crate) fn new(backend: PoolBackend) -> Self {
+        match backend {
+            PoolBackend::Heap => Deadlines::Inline(SmallVec::new()),
+            PoolBackend::TimerWheel { resolution } => Deadlines::Wheel(Box::new(TimerWheel::new(resolution))),
+        }
+    }
+
+    pub(crate) fn backend(&self) -> PoolBackend {
+        match self {
+            Deadlines::Inline(_) | Deadlines::Heap(_) => PoolBackend::Heap,
+            Deadlines::Wheel(wheel) => PoolBackend::TimerWheel { resolution: wheel.resolution },
+        }
+    }
+
+    pub(crate) fn push(&mut self, deadline: Duration, handle: MessageHandle) {
+        match self {
+            Deadlines::Inline(inline) if inline.len() < INLINE => inline.push((deadline, handle)),
+            Deadlines::Inline(inline) => {
+                let mut heap: BinaryHeap<_> = inline.drain(..).map(Reverse).collect();
+                heap.push(Reverse((deadline, handle)));
+                *self = Deadlines::Heap(heap);
+            }
+            Deadlines::Heap(heap) => heap.push(Reverse((deadline, handle))),
+            Deadlines::Wheel(wheel) => wheel.push(deadline, handle),
+        }
+    }
+
+    /// Moves every entry with a deadline at or before `now` into `due`, in no particular order.
+    pub(crate) fn pop_due(&mut self, now: Duration, due: &mut Vec<(Duration, MessageHandle)>) {
+        match self {
+            Deadlines::Inline(inline) => inline.retain(|entry| {
+                let is_due = entry.0 <= now;
+                if is_due {
+                    due.push(*entry);
+                }
+                !is_due
+            }),
+            Deadlines::Heap(heap) => {
+                while let Some(&Reverse(entry)) = heap.peek() && entry.0 <= now {
+                    heap.pop();
+                    due.push(entry);
+                }
+            }
+            Deadlines::Wheel(wheel) => wheel.pop_due(now, due),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Deadlines::Inline(inline) => inline.len(),
+            Deadlines::Heap(heap) => heap.len(),
+            Deadlines::Wheel(wheel) => wheel.len,
+        }
+    }
+
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(Duration, MessageHandle) -> bool) {
+        match self {
+            Deadlines::Inline(inline) => inline.retain(|&mut (deadline, handle)| f(deadline, handle)),
+            Deadlines::Heap(heap) => heap.retain(|&Reverse((deadline, handle))| f(deadline, handle)),
+            Deadlines::Wheel(wheel) => wheel.retain(f),
+        }
+    }
+
+    pub(crate) fn drain(&mut self) -> Vec<(Duration, MessageHandle)> {
+        match self {
+            Deadlines::Inline(inline) => inline.drain(..).collect(),
+            Deadlines::Heap(heap) => heap.drain().map(|Reverse(entry)| entry).collect(),
+            Deadlines::Wheel(wheel) => {
+                let mut entries = Vec::with_capacity(wheel.len);
+                wheel.retain(|deadline, handle| {
+                    entries.push((deadline, handle));
+                    false
+                });
+                entries
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        match self {
+            Deadlines::Inline(inline) => inline.clear(),
+            Deadlines::Heap(heap) => heap.clear(),
+            Deadlines::Wheel(wheel) => wheel.retain(|_, _| false),
+        }
+    }
+}
+
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: usize = 6;
+
+/// Hierarchical timer wheel. Level `n` has [`SLOTS`] slots each covering `SLOTS^n` ticks of `resolution`.
+///
+/// An entry lives on the lowest level where its tick and the current tick share every higher digit,
+/// and moves down a level whenever the current tick reaches the slot it sits in.
+pub(crate) struct TimerWheel {
+    resolution: Duration,
+    /// Every tick before this one has been emptied.
+    current: u64,
+    levels: [[Vec<(Duration, MessageHandle)>; SLOTS]; LEVELS],
+    level_lens: [usize; LEVELS],
+    len: usize,
+}
+
+impl TimerWheel {
+    fn new(resolution: Duration) -> Self {
+        Self {
+            resolution: resolution.max(Duration::from_nanos(1)),
+            current: 0,
+            levels: std::array::from_fn(|_| std::array::from_fn(|_| Vec::new())),
+            level_lens: [0; LEVELS],
+            len: 0,
+        }
+    }
+
+    fn tick_of(&self, time: Duration) -> u64 {
+        u64::try_from(time.as_nanos() / self.resolution.as_nanos()).unwrap_or(u64::MAX)
+    }
+
+    fn push(&mut self, deadline: Duration, handle: MessageHandle) {
+        let tick = self.tick_of(deadline).max(self.current);
+        let differing = tick ^ self.current;
+        let level = if differing == 0 {
+            0
+        } else {
+            ((u64::BITS - 1 - differing.leading_zeros()) / SLOT_BITS) as usize
+        }
+        .min(LEVELS - 1);
+        let slot = (tick >> (SLOT_BITS * level as u32)) as usize % SLOTS;
+        self.levels[level][slot].push((deadline, handle));
+        self.level_lens[level] += 1;
+        self.len += 1;
+    }
+
+    fn pop_due(&mut self, now: Duration, due: &mut Vec<(Duration, MessageHandle)>) {
+        let target = self.tick_of(now);
+        loop {
+            let slot = &mut self.levels[0][self.current as usize % SLOTS];
+            if !slot.is_empty() {
+                let before = slot.len();
+                slot.retain(|&entry| {
+                    let is_due = entry.0 <= now;
+                    if is_due {
+                        due.push(entry);
+                    }
+                    !is_due
+                });
+                let popped = before - slot.len();
+                self.level_lens[0] -= popped;
+                self.len -= popped;
+            }
+            if self.current >= target {
+                return;
+            }
+
+            // Jump straight to the next slot boundary of the lowest non-empty level.
+            let empty_levels = self.level_lens.iter().take_while(|&&len| len == 0).count().min(LEVELS - 1) as u32;
+            let span = 1u64 << (SLOT_BITS * empty_levels);
+            self.current = (self.current / span).saturating_add(1).saturating_mul(span).min(target);
+
+            for level in (1..LEVELS).rev() {
+                let shift = SLOT_BITS * level as u32;
+                if self.current & ((1 << shift) - 1) != 0 {
+                    continue;
+                }
+                let slot = (self.current >> shift) as usize % SLOTS;
+                let entries = std::mem::take(&mut self.levels[level][slot]);
+                self.level_lens[level] -= entries.len();
+                self.len -= entries.len();
+                for (deadline, handle) in entries {
+                    self.push(deadline, handle);
+                }
+            }
+        }
+    }
+
+    fn retain(&mut self, mut f: impl FnMut(Duration, MessageHandle) -> bool) {
+        for (level, slots) in self.levels.iter_mut().enumerate() {
+            for slot in slots {
+                let before = slot.len();
+                slot.retain(|&(deadline, handle)| f(deadline, handle));
+                self.level_lens[level] -= before - slot.len();
+                self.len -= before - slot.len();
+            }
+        }
+    }
+}
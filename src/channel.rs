@@ -0,0 +1,137 @@
+//! Scheduling from threads and async tasks without access to the world.
+
+use std::future::Future;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::platform::cell::SyncCell;
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+
+use crate::{IntoDelay, MessagePool, with_global_pool};
+
+type Schedule = Box<dyn FnOnce(&mut MessagePool) + Send + 'static>;
+
+/// Cloneable handle scheduling into pools from any thread, taken from the resource of the same
+/// type that [`WriteAfterPlugin`](crate::WriteAfterPlugin) inserts.
+///
+/// Messages are moved into their pool at the start of the next processing pass, their delay
+/// starts from there. Messages sent after the app was dropped are dropped too.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_write_after::WriteAfterSender;
+/// # #[derive(Message)]
+/// # struct PathFound;
+/// fn start_pathfinding(sender: Res<WriteAfterSender>) {
+///     let sender = sender.clone();
+///     std::thread::spawn(move || {
+///         sender.write_after(PathFound, 0.2);
+///     });
+/// }
+/// # bevy::ecs::system::assert_is_system(start_pathfinding);
+/// ```
+#[derive(Resource, Clone)]
+pub struct WriteAfterSender {
+    sender: Sender<(Option<Entity>, Schedule)>,
+}
+
+impl WriteAfterSender {
+    /// Writes `message` after `delay` from the [`GlobalMessagePool`](crate::GlobalMessagePool).
+    pub fn write_after<M: Message>(&self, message: M, delay: impl IntoDelay) {
+        self.send(None, message, delay.into_delay());
+    }
+
+    /// Writes `message` after `delay` from the [`MessagePool`] of `pool`.
+    ///
+    /// The message is dropped with a warning if `pool` has no `MessagePool` by then.
+    pub fn write_after_to<M: Message>(&self, pool: Entity, message: M, delay: impl IntoDelay) {
+        self.send(Some(pool), message, delay.into_delay());
+    }
+
+    fn send<M: Message>(&self, pool: Option<Entity>, message: M, delay: Duration) {
+        let schedule = Box::new(move |pool: &mut MessagePool| {
+            pool.write_after(message, delay);
+        });
+        // Fails only once the app, and so every pool, is gone.
+        let _ = self.sender.send((pool, schedule));
+    }
+}
+
+type Delayed = Box<dyn FnOnce(&mut MessagePool, Duration) + Send + 'static>;
+
+/// Handle to the [`GlobalMessagePool`](crate::GlobalMessagePool) for background tasks, like
+/// asset processing or pathfinding on the `AsyncComputeTaskPool`, taken from the resource of
+/// the same type that [`WriteAfterPlugin`](crate::WriteAfterPlugin) inserts.
+///
+/// Unlike with [`WriteAfterSender`], delays start when the handle is called: the real time
+/// until the message reaches the pool is taken off its delay.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy::tasks::AsyncComputeTaskPool;
+/// # use bevy_write_after::GlobalWriteAfter;
+/// # #[derive(Message)]
+/// # struct PathFound(Vec<Vec2>);
+/// # fn find_path() -> Vec<Vec2> { Vec::new() }
+/// fn start_pathfinding(write_after: Res<GlobalWriteAfter>) {
+///     let write_after = write_after.clone();
+///     AsyncComputeTaskPool::get()
+///         .spawn(async move {
+///             // Delivered 0.2s after the path is found.
+///             write_after.write_after_completion(async { PathFound(find_path()) }, 0.2).await;
+///         })
+///         .detach();
+/// }
+/// # bevy::ecs::system::assert_is_system(start_pathfinding);
+/// ```
+#[derive(Resource, Clone, Default)]
+pub struct GlobalWriteAfter {
+    queue: Arc<Mutex<Vec<(Instant, Delayed)>>>,
+}
+
+impl GlobalWriteAfter {
+    /// Writes `message` after `delay`, counted from now.
+    pub fn write_after<M: Message>(&self, message: M, delay: impl IntoDelay) {
+        let delay = delay.into_delay();
+        let schedule = Box::new(move |pool: &mut MessagePool, late: Duration| {
+            pool.write_after(message, delay.saturating_sub(late));
+        });
+        self.queue.lock().unwrap().push((Instant::now(), schedule));
+    }
+
+    /// Awaits `task`, then writes the message it resolves to `delay` after its completion.
+    pub async fn write_after_completion<M: Message>(&self, task: impl Future<Output = M>, delay: impl IntoDelay) {
+        let delay = delay.into_delay();
+        self.write_after(task.await, delay);
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct WriteAfterReceiver(SyncCell<Receiver<(Option<Entity>, Schedule)>>);
+
+pub(crate) fn write_after_channel() -> (WriteAfterSender, WriteAfterReceiver) {
+    let (sender, receiver) = channel();
+    (WriteAfterSender { sender }, WriteAfterReceiver(SyncCell::new(receiver)))
+}
+
+/// Moves everything sent with a [`WriteAfterSender`] or a [`GlobalWriteAfter`] into its pool.
+pub(crate) fn receive_scheduled(world: &mut World) {
+    let queued = std::mem::take(&mut *world.resource::<GlobalWriteAfter>().queue.lock().unwrap());
+    for (sent, schedule) in queued {
+        with_global_pool(world, |pool| schedule(pool, sent.elapsed()));
+    }
+
+    let received: Vec<_> = world.resource_mut::<WriteAfterReceiver>().0.get().try_iter().collect();
+    for (pool, schedule) in received {
+        let Some(entity) = pool else {
+            with_global_pool(world, schedule);
+            continue;
+        };
+        match world.get_mut::<MessagePool>(entity) {
+            Some(mut pool) => schedule(&mut pool),
+            None => warn!("dropped message sent to {entity}, it has no MessagePool"),
+        }
+    }
+}
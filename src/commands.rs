@@ -0,0 +1,146 @@
+//! Command extensions scheduling into the [`GlobalMessagePool`](crate::GlobalMessagePool).
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::named::with_named_pool;
+use crate::{DespawnAfter, IntoDelay, MessagePool, advance_pool_ticks, with_global_pool};
+
+/// Extension trait for [`Commands`] to schedule into the global pool without querying for it.
+pub trait WriteAfterCommandsExt {
+    /// Writes `message` after `delay`, see [`MessagePool::write_after`](crate::MessagePool::write_after).
+    fn write_message_after<M: Message + Send + Sync + 'static>(&mut self, message: M, delay: impl IntoDelay) -> &mut Self;
+
+    /// Writes `message` after `delay` from the pool registered under `label`, see
+    /// [`WriteAfterAppExt::add_message_pool`](crate::WriteAfterAppExt::add_message_pool).
+    ///
+    /// The command panics if no pool is registered under `label`.
+    fn write_message_after_in<M: Message + Send + Sync + 'static>(
+        &mut self,
+        label: impl Into<Cow<'static, str>>,
+        message: M,
+        delay: impl IntoDelay,
+    ) -> &mut Self;
+}
+
+impl WriteAfterCommandsExt for Commands<'_, '_> {
+    fn write_message_after<M: Message + Send + Sync + 'static>(&mut self, message: M, delay: impl IntoDelay) -> &mut Self {
+        let delay = delay.into_delay();
+        self.queue(move |world: &mut World| {
+            with_global_pool(world, |pool| {
+                pool.write_after(message, delay);
+            });
+        });
+        self
+    }
+
+    fn write_message_after_in<M: Message + Send + Sync + 'static>(
+        &mut self,
+        label: impl Into<Cow<'static, str>>,
+        message: M,
+        delay: impl IntoDelay,
+    ) -> &mut Self {
+        let label = label.into();
+        let delay = delay.into_delay();
+        self.queue(move |world: &mut World| {
+            with_named_pool(world, &label, |pool| {
+                pool.write_after(message, delay);
+            });
+        });
+        self
+    }
+}
+
+/// Extension trait for [`EntityCommands`] to change the entity after a delay.
+pub trait WriteAfterEntityCommandsExt {
+    /// Inserts `bundle` on the entity after `delay`, unless the entity no longer exists by then.
+    fn insert_after(&mut self, bundle: impl Bundle, delay: impl IntoDelay) -> &mut Self;
+
+    /// Removes the components of `B` from the entity after `delay`, unless the entity no longer exists by then.
+    fn remove_after<B: Bundle>(&mut self, delay: impl IntoDelay) -> &mut Self;
+
+    /// Despawns the entity after `delay` by inserting [`DespawnAfter`].
+    fn despawn_after(&mut self, delay: impl IntoDelay) -> &mut Self;
+
+    /// Schedules into the entity's own [`MessagePool`], inserting one if it doesn't have it yet.
+    fn with_pool(&mut self) -> EntityPoolCommands<'_>;
+
+    /// Advances the entity's tick based [`MessagePool`] by `ticks` ticks, writing what becomes due
+    /// in order. See [`MessagePool::with_ticks`](crate::MessagePool::with_ticks).
+    fn advance_ticks(&mut self, ticks: u32) -> &mut Self;
+
+    /// Same as [`WriteAfterEntityCommandsExt::advance_ticks`], for the [`MarkedMessagePool<T>`](crate::MarkedMessagePool) of the entity.
+    fn advance_marked_ticks<T: 'static>(&mut self, ticks: u32) -> &mut Self;
+}
+
+impl WriteAfterEntityCommandsExt for EntityCommands<'_> {
+    fn insert_after(&mut self, bundle: impl Bundle, delay: impl IntoDelay) -> &mut Self {
+        entity_after(self, delay.into_delay(), move |mut entity| {
+            entity.insert(bundle);
+        })
+    }
+
+    fn remove_after<B: Bundle>(&mut self, delay: impl IntoDelay) -> &mut Self {
+        entity_after(self, delay.into_delay(), |mut entity| {
+            entity.remove::<B>();
+        })
+    }
+
+    fn despawn_after(&mut self, delay: impl IntoDelay) -> &mut Self {
+        self.insert(DespawnAfter::new(delay))
+    }
+
+    fn with_pool(&mut self) -> EntityPoolCommands<'_> {
+        EntityPoolCommands { commands: self.reborrow() }
+    }
+
+    fn advance_ticks(&mut self, ticks: u32) -> &mut Self {
+        self.queue(move |entity: EntityWorldMut| {
+            let id = entity.id();
+            advance_pool_ticks::<()>(entity.into_world_mut(), id, ticks);
+        })
+    }
+
+    fn advance_marked_ticks<T: 'static>(&mut self, ticks: u32) -> &mut Self {
+        self.queue(move |entity: EntityWorldMut| {
+            let id = entity.id();
+            advance_pool_ticks::<T>(entity.into_world_mut(), id, ticks);
+        })
+    }
+}
+
+/// Schedules into an entity's own [`MessagePool`], returned by [`WriteAfterEntityCommandsExt::with_pool`].
+pub struct EntityPoolCommands<'a> {
+    commands: EntityCommands<'a>,
+}
+
+impl EntityPoolCommands<'_> {
+    /// Writes `message` after `delay`, see [`MessagePool::write_after`].
+    pub fn write_after<M: Message + Send + Sync + 'static>(&mut self, message: M, delay: impl IntoDelay) -> &mut Self {
+        let delay = delay.into_delay();
+        self.commands.queue(move |mut entity: EntityWorldMut| {
+            entity.entry::<MessagePool>().or_default().get_mut().write_after(message, delay);
+        });
+        self
+    }
+}
+
+fn entity_after<'a, 'b>(
+    commands: &'a mut EntityCommands<'b>,
+    delay: Duration,
+    f: impl FnOnce(EntityWorldMut) + Send + 'static,
+) -> &'a mut EntityCommands<'b> {
+    let entity = commands.id();
+    commands.commands().queue(move |world: &mut World| {
+        with_global_pool(world, |pool| {
+            pool.command_after_fn(move |world: &mut World| {
+                if let Ok(entity) = world.get_entity_mut(entity) {
+                    f(entity);
+                }
+            }, delay);
+        });
+    });
+    commands
+}
@@ -0,0 +1,83 @@
+//! Futures resolving once a scheduled message was written or cancelled.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// What became of a message scheduled with
+/// [`MessagePool::write_after_notify`](crate::MessagePool::write_after_notify).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageOutcome {
+    Written,
+    /// Cancelled, dropped from a full pool or dropped with its pool.
+    Cancelled,
+}
+
+#[derive(Default)]
+struct Shared {
+    outcome: Option<MessageOutcome>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving to the [`MessageOutcome`] of a message, returned by
+/// [`MessagePool::write_after_notify`](crate::MessagePool::write_after_notify).
+///
+/// It can be awaited from any task, like ones spawned on the `AsyncComputeTaskPool`.
+pub struct MessageCompletion {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl MessageCompletion {
+    /// The outcome if the message was already written or cancelled, for systems polling it.
+    pub fn outcome(&self) -> Option<MessageOutcome> {
+        self.shared.lock().unwrap().outcome
+    }
+}
+
+impl Future for MessageCompletion {
+    type Output = MessageOutcome;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<MessageOutcome> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.outcome {
+            Some(outcome) => Poll::Ready(outcome),
+            None => {
+                shared.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Resolves its [`MessageCompletion`], as cancelled if dropped before [`CompletionSender::written`].
+pub(crate) struct CompletionSender {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl CompletionSender {
+    pub(crate) fn written(self) {
+        self.resolve(MessageOutcome::Written);
+    }
+
+    fn resolve(&self, outcome: MessageOutcome) {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.outcome.is_none() {
+            shared.outcome = Some(outcome);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Drop for CompletionSender {
+    fn drop(&mut self) {
+        self.resolve(MessageOutcome::Cancelled);
+    }
+}
+
+pub(crate) fn completion() -> (CompletionSender, MessageCompletion) {
+    let shared = Arc::new(Mutex::new(Shared::default()));
+    (CompletionSender { shared: shared.clone() }, MessageCompletion { shared })
+}
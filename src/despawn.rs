@@ -0,0 +1,76 @@
+//! Timed despawning built on the [`GlobalMessagePool`](crate::GlobalMessagePool).
+
+use std::time::Duration;
+
+use bevy::ecs::lifecycle::HookContext;
+use bevy::ecs::world::DeferredWorld;
+use bevy::prelude::*;
+
+use crate::{IntoDelay, MessageHandle, with_global_pool, write_message};
+
+type BeforeDespawn = Box<dyn FnOnce(&mut World) + Send + Sync + 'static>;
+
+/// Despawns the entity after a delay.
+///
+/// The delay starts when the component is inserted. Removing the component before the delay
+/// completes keeps the entity alive, inserting it again restarts the delay.
+#[derive(Component)]
+#[component(on_insert = schedule_despawn, on_replace = cancel_despawn)]
+pub struct DespawnAfter {
+    delay: Duration,
+    before_despawn: Option<BeforeDespawn>,
+    scheduled: Option<MessageHandle>,
+}
+
+impl DespawnAfter {
+    pub fn new(delay: impl IntoDelay) -> Self {
+        Self {
+            delay: delay.into_delay(),
+            before_despawn: None,
+            scheduled: None,
+        }
+    }
+
+    /// Writes `message` right before the entity is despawned.
+    pub fn with_message<M: Message + Send + Sync + 'static>(mut self, message: M) -> Self {
+        self.before_despawn = Some(Box::new(move |world: &mut World| {
+            write_message(world, message);
+        }));
+        self
+    }
+}
+
+fn schedule_despawn(mut world: DeferredWorld, context: HookContext) {
+    let entity = context.entity;
+    let Some(mut despawn_after) = world.get_mut::<DespawnAfter>(entity) else {
+        return;
+    };
+    let delay = despawn_after.delay;
+    let before_despawn = despawn_after.before_despawn.take();
+
+    world.commands().queue(move |world: &mut World| {
+        let handle = with_global_pool(world, |pool| {
+            pool.command_after_fn(move |world: &mut World| {
+                if world.get_entity(entity).is_err() {
+                    return;
+                }
+                if let Some(before_despawn) = before_despawn {
+                    before_despawn(world);
+                }
+                world.despawn(entity);
+            }, delay)
+        });
+        if let Some(mut despawn_after) = world.get_mut::<DespawnAfter>(entity) {
+            despawn_after.scheduled = Some(handle);
+        }
+    });
+}
+
+fn cancel_despawn(mut world: DeferredWorld, context: HookContext) {
+    let Some(handle) = world.get::<DespawnAfter>(context.entity).and_then(|despawn_after| despawn_after.scheduled) else {
+        return;
+    };
+    world.commands().queue(move |world: &mut World| {
+        with_global_pool(world, |pool| pool.cancel(handle));
+    });
+}
@@ -0,0 +1,53 @@
+//! Pool statistics reported to Bevy's diagnostics.
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+
+use crate::{MessagePool, WriteAfterSet, WrittenCounter};
+
+/// Reports statistics of the unmarked [`MessagePool`]s to the [`DiagnosticsStore`](bevy::diagnostic::DiagnosticsStore),
+/// so they are logged by `LogDiagnosticsPlugin` and shown by overlays alongside the FPS.
+///
+/// Requires [`WriteAfterPlugin`](crate::WriteAfterPlugin).
+#[derive(Default)]
+pub struct WriteAfterDiagnosticsPlugin;
+
+impl WriteAfterDiagnosticsPlugin {
+    /// Messages pending in all pools.
+    pub const PENDING_MESSAGES: DiagnosticPath = DiagnosticPath::const_new("write_after/pending_messages");
+    /// Messages written per second of real time.
+    pub const MESSAGES_PER_SECOND: DiagnosticPath = DiagnosticPath::const_new("write_after/messages_per_second");
+    /// Pools holding at least one pending message.
+    pub const ACTIVE_POOLS: DiagnosticPath = DiagnosticPath::const_new("write_after/active_pools");
+}
+
+impl Plugin for WriteAfterDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<WrittenCounter>()
+            .register_diagnostic(Diagnostic::new(Self::PENDING_MESSAGES))
+            .register_diagnostic(Diagnostic::new(Self::MESSAGES_PER_SECOND).with_suffix("/s"))
+            .register_diagnostic(Diagnostic::new(Self::ACTIVE_POOLS))
+            .add_systems(Update, measure_pools.after(WriteAfterSet));
+    }
+}
+
+fn measure_pools(
+    mut diagnostics: Diagnostics,
+    real_time: Option<Res<Time<Real>>>,
+    counter: Res<WrittenCounter>,
+    pools: Query<&MessagePool>,
+) {
+    diagnostics.add_measurement(&WriteAfterDiagnosticsPlugin::PENDING_MESSAGES, || {
+        pools.iter().map(MessagePool::len).sum::<usize>() as f64
+    });
+    diagnostics.add_measurement(&WriteAfterDiagnosticsPlugin::ACTIVE_POOLS, || {
+        pools.iter().filter(|pool| !pool.is_empty()).count() as f64
+    });
+
+    let written = counter.take();
+    let delta = real_time.map(|time| time.delta_secs_f64()).unwrap_or_default();
+    if delta > 0.0 {
+        diagnostics.add_measurement(&WriteAfterDiagnosticsPlugin::MESSAGES_PER_SECOND, || written as f64 / delta);
+    }
+}
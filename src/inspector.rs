@@ -0,0 +1,117 @@
+//! Window listing the pools and their pending messages, drawn with `bevy_egui`.
+
+use bevy::prelude::*;
+use bevy::utils::prelude::ShortName;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{GlobalMessagePool, MessageHandle, MessagePool, PausedPool};
+
+/// Shows the unmarked [`MessagePool`]s in a window of the primary egui context, with the
+/// `inspector` feature.
+///
+/// Every pending message is listed with its type, label and remaining time, soonest first,
+/// next to buttons to cancel it, write it now, or pause and resume it. Pools can be paused
+/// with [`PausedPool`] from their header.
+///
+/// Requires `EguiPlugin` and [`WriteAfterPlugin`](crate::WriteAfterPlugin).
+#[derive(Default)]
+pub struct WriteAfterInspectorPlugin;
+
+impl WriteAfterInspectorPlugin {
+    /// Title of the window, which is also its egui id.
+    pub const TITLE: &'static str = "Message pools";
+}
+
+impl Plugin for WriteAfterInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(EguiPrimaryContextPass, inspect_pools);
+    }
+}
+
+type InspectedPool = (
+    Entity,
+    &'static mut MessagePool,
+    Option<&'static Name>,
+    Has<GlobalMessagePool>,
+    Has<PausedPool>,
+);
+
+#[derive(Clone, Copy)]
+enum Action {
+    Cancel,
+    FireNow,
+    Pause,
+    Resume,
+}
+
+fn inspect_pools(
+    mut contexts: EguiContexts,
+    mut pools: Query<InspectedPool>,
+    mut commands: Commands,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+    let mut sorted: Vec<_> = pools.iter_mut().collect();
+    sorted.sort_by_key(|(entity, ..)| entity.index());
+
+    egui::Window::new(WriteAfterInspectorPlugin::TITLE).show(context, |ui| {
+        if sorted.is_empty() {
+            ui.label("No pools");
+        }
+        for (entity, pool, name, global, paused) in &mut sorted {
+            let title = match (name, *global) {
+                (Some(name), _) => format!("{name} ({entity})"),
+                (None, true) => format!("Global ({entity})"),
+                (None, false) => entity.to_string(),
+            };
+            let mut actions: Vec<(MessageHandle, Action)> = Vec::new();
+            egui::CollapsingHeader::new(format!("{title}: {} pending", pool.len()))
+                .id_salt(*entity)
+                .default_open(true)
+                .show(ui, |ui| {
+                    let mut pool_paused = *paused;
+                    if ui.checkbox(&mut pool_paused, "Paused").changed() {
+                        if pool_paused {
+                            commands.entity(*entity).insert(PausedPool);
+                        } else {
+                            commands.entity(*entity).remove::<PausedPool>();
+                        }
+                    }
+
+                    let mut pending: Vec<_> = pool.iter_pending().collect();
+                    pending.sort_by_key(|message| (message.remaining, message.handle.0));
+                    egui::Grid::new(*entity).striped(true).show(ui, |ui| {
+                        for message in pending {
+                            ui.label(ShortName(message.type_name).to_string());
+                            ui.label(message.label.unwrap_or_default());
+                            ui.label(format!("{:.2}s", message.remaining.as_secs_f32()));
+                            if ui.small_button("Cancel").clicked() {
+                                actions.push((message.handle, Action::Cancel));
+                            }
+                            if ui.small_button("Fire now").clicked() {
+                                actions.push((message.handle, Action::FireNow));
+                            }
+                            let (toggle, action) = match message.paused {
+                                true => ("Resume", Action::Resume),
+                                false => ("Pause", Action::Pause),
+                            };
+                            if ui.small_button(toggle).clicked() {
+                                actions.push((message.handle, action));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            for (handle, action) in actions {
+                match action {
+                    Action::Cancel => pool.cancel(handle),
+                    Action::FireNow => pool.fire_now(handle),
+                    Action::Pause => pool.pause(handle),
+                    Action::Resume => pool.resume(handle),
+                };
+            }
+        }
+    });
+}
@@ -13,7 +13,7 @@
 //! fn my_main() {
 //!     App::new()
 //!         .add_message::<MyMessage>()
-//!         .add_plugins(WriteAfterPlugin)
+//!         .add_plugins(WriteAfterPlugin::default())
 //!         .add_systems(Startup, setup)
 //!         .add_systems(Startup, some_system)
 //!         .add_systems(Update, on_my_message.run_if(on_message::<MyMessage>));
@@ -35,152 +35,5554 @@
 //! }
 //!
 //! ```
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::ecs::message::{MessageRegistry, MessageUpdateSystems};
+use bevy::ecs::component::Tick;
+use bevy::ecs::lifecycle::HookContext;
+use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
+use bevy::ecs::world::CommandQueue;
+use bevy::ecs::entity::Entities;
+use bevy::ecs::system::{SystemChangeTick, SystemId, SystemInput, SystemParam};
+use bevy::ecs::world::DeferredWorld;
+use bevy::platform::cell::SyncCell;
+use bevy::platform::hash::FixedHasher;
+use bevy::reflect::TypeRegistry;
+use bevy::state::state::FreelyMutableState;
+use bevy::time::{TimePlugin, TimeSystems};
+use bevy::utils::Parallel;
 use bevy::prelude::*;
 
-pub struct WriteAfterPlugin;
+use backend::Deadlines;
+use slab::Slab;
+
+mod backend;
+mod channel;
+mod commands;
+mod completion;
+mod despawn;
+mod diagnostics;
+#[cfg(feature = "inspector")]
+mod inspector;
+mod named;
+mod param;
+#[cfg(feature = "replicon")]
+mod replicon;
+mod script;
+mod slab;
+mod snapshot;
+mod state;
+mod testing;
+#[cfg(feature = "timeline")]
+mod timeline;
+mod typed;
+
+pub use backend::PoolBackend;
+pub use channel::{GlobalWriteAfter, WriteAfterSender};
+pub use commands::{EntityPoolCommands, WriteAfterCommandsExt, WriteAfterEntityCommandsExt};
+pub use completion::{MessageCompletion, MessageOutcome};
+pub use despawn::DespawnAfter;
+pub use diagnostics::WriteAfterDiagnosticsPlugin;
+#[cfg(feature = "inspector")]
+pub use inspector::WriteAfterInspectorPlugin;
+pub use named::{MessagePools, WriteAfterAppExt};
+pub use param::{DelayedMessageWriter, WriteAfter};
+#[cfg(feature = "replicon")]
+pub use replicon::{ReplicatedMessage, ReplicatedSchedule, WriteAfterReplicationPlugin};
+pub use script::{Script, ScriptPlayer, ScriptPlugin};
+pub use snapshot::{PoolSnapshot, ReflectDelayedMessage, SavedMessage, SavedPool};
+#[cfg(feature = "serialize")]
+pub use snapshot::{PoolSnapshotDeserializer, PoolSnapshotSerializer};
+pub use state::{ClearPoolOnExit, FlushPoolOnExit};
+pub use testing::WriteAfterTestExt;
+#[cfg(feature = "timeline")]
+pub use timeline::{TimelineAsset, TimelineEntry, TimelinePlugin};
+pub use typed::{TypedMessageHandle, TypedMessagePool, TypedMessagePoolPlugin};
+
+/// Processes every unmarked [`MessagePool`].
+///
+/// Apps without `TimePlugin` get a `Time` following the real time passed, unless they insert
+/// their own `Time` before the app finishes building, as tests advancing it by hand do.
+pub struct WriteAfterPlugin {
+    /// Schedule processing pools on the frame based clocks, [`PoolClock::Virtual`] and [`PoolClock::Real`].
+    ///
+    /// Defaults to `Update`. [`PoolClock::Fixed`] pools and tick based delays are always processed in `FixedUpdate`.
+    pub schedule: InternedScheduleLabel,
+    /// Whether to spawn the [`GlobalMessagePool`] in `PreStartup`. Defaults to `true`.
+    ///
+    /// Without it, the global pool is only spawned once something schedules into it, like
+    /// [`WriteAfterCommandsExt::write_message_after`] or [`DespawnAfter`].
+    pub spawn_global_pool: bool,
+    /// What happens when a pool writes a message whose type isn't registered with `add_message`.
+    pub unregistered_messages: UnregisteredMessagePolicy,
+    /// Conditions freezing every pool while one of them holds, added by [`WriteAfterPlugin::pause_in_state`].
+    pub paused_in: Vec<PauseCondition>,
+}
+
+impl Default for WriteAfterPlugin {
+    fn default() -> Self {
+        Self {
+            schedule: Update.intern(),
+            spawn_global_pool: true,
+            unregistered_messages: UnregisteredMessagePolicy::default(),
+            paused_in: Vec::new(),
+        }
+    }
+}
+
+impl WriteAfterPlugin {
+    /// Processes the pools in `schedule` instead of `Update`.
+    ///
+    /// [`PoolClock::Virtual`] pools advance by the generic [`Time`] of that schedule, so in
+    /// `FixedUpdate` they follow `Time<Fixed>`.
+    pub fn in_schedule(schedule: impl ScheduleLabel) -> Self {
+        Self {
+            schedule: schedule.intern(),
+            ..default()
+        }
+    }
+
+    /// Processes the pools in `First`, right after the clocks were updated, so every system of the
+    /// frame can read the messages written by a pool in that frame.
+    pub fn early() -> Self {
+        Self::in_schedule(First)
+    }
+
+    /// Doesn't spawn the [`GlobalMessagePool`] on startup, see [`WriteAfterPlugin::spawn_global_pool`].
+    pub fn without_global_pool(mut self) -> Self {
+        self.spawn_global_pool = false;
+        self
+    }
+
+    /// Sets [`WriteAfterPlugin::unregistered_messages`].
+    pub fn on_unregistered_message(mut self, policy: UnregisteredMessagePolicy) -> Self {
+        self.unregistered_messages = policy;
+        self
+    }
+
+    /// Freezes every pool, marked and typed ones included, while the app is in `state`.
+    ///
+    /// Works like [`PausedPool`] on each of them. Can be called for several states.
+    pub fn pause_in_state<S: States>(mut self, state: S) -> Self {
+        self.paused_in.push(in_state_condition(state));
+        self
+    }
+}
+
+/// Condition on the world pausing pools, see [`WriteAfterPlugin::paused_in`].
+pub type PauseCondition = Arc<dyn Fn(&World) -> bool + Send + Sync>;
+
+fn in_state_condition<S: States>(state: S) -> PauseCondition {
+    Arc::new(move |world: &World| world.get_resource::<State<S>>().is_some_and(|current| *current.get() == state))
+}
+
+/// Conditions freezing the pools marked with `T`, every pool for `()`.
+#[derive(Resource)]
+struct PausingStates<T: 'static = ()> {
+    conditions: Vec<PauseCondition>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for PausingStates<T> {
+    fn default() -> Self {
+        Self {
+            conditions: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static> PausingStates<T> {
+    fn add(app: &mut App, conditions: &[PauseCondition]) {
+        app.world_mut().get_resource_or_init::<Self>().conditions.extend(conditions.iter().cloned());
+    }
+
+    fn is_paused(world: &World) -> bool {
+        world.get_resource::<Self>().is_some_and(|states| states.conditions.iter().any(|condition| condition(world)))
+    }
+}
+
+/// Run condition of the processing systems of the pools marked with `T`.
+fn pools_running<T: 'static>(world: &World) -> bool {
+    !PausingStates::<()>::is_paused(world) && !PausingStates::<T>::is_paused(world)
+}
 
 impl Plugin for WriteAfterPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = channel::write_after_channel();
+        app
+            .add_message::<MessagePoolEmptied>()
+            .add_message::<MessagePoolOverflowed>()
+            .init_resource::<WriteAfterTimeScale>()
+            .insert_resource(self.unregistered_messages)
+            .insert_resource(ProcessingSchedule(self.schedule))
+            .insert_resource(sender)
+            .insert_resource(receiver)
+            .init_resource::<GlobalWriteAfter>()
+            .register_type::<MessagePool>()
+            .register_type::<MessagePoolConfig>()
+            .register_type::<PoolClock>()
+            .register_type::<SavedPool>()
+            .configure_sets(self.schedule, WriteAfterSet.after(TimeSystems).after(MessageUpdateSystems))
+            .add_systems(self.schedule, channel::receive_scheduled.before(WriteAfterSet));
+        PausingStates::<()>::add(app, &self.paused_in);
+        add_pool_systems::<()>(app, self.schedule);
+
+        if self.spawn_global_pool {
+            app.add_systems(PreStartup, spawn_global_message_pool);
+        }
+    }
+
+    fn finish(&self, app: &mut App) {
+        // Without `TimePlugin` nothing would advance `Time`, or even create it.
+        if !app.is_plugin_added::<TimePlugin>() && !app.world().contains_resource::<Time>() {
+            app
+                .init_resource::<Time>()
+                .init_resource::<Time<Real>>()
+                .add_systems(First, drive_headless_time.in_set(TimeSystems));
+        }
+    }
+}
+
+/// [`WriteAfterPlugin::schedule`], for the plugins processing pools or reacting to them.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct ProcessingSchedule(InternedScheduleLabel);
+
+impl ProcessingSchedule {
+    /// Schedule of the pools of `app`, `Update` if [`WriteAfterPlugin`] wasn't added before.
+    pub(crate) fn of(app: &App) -> InternedScheduleLabel {
+        app.world().get_resource::<Self>().map_or(Update.intern(), |schedule| schedule.0)
+    }
+}
+
+/// Advances `Time` by the real time passed, in apps without `TimePlugin`.
+fn drive_headless_time(mut real_time: ResMut<Time<Real>>, mut time: ResMut<Time>) {
+    real_time.update();
+    time.advance_by(real_time.delta());
+}
+
+/// Registers the processing of every [`MarkedMessagePool<T>`], the counterpart of [`WriteAfterPlugin`]
+/// for marked pools. Pools on the frame based clocks are processed in [`WriteAfterPlugin::schedule`]
+/// if it was added before, in `Update` otherwise.
+pub struct MessagePoolPlugin<T> {
+    paused_in: Vec<PauseCondition>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for MessagePoolPlugin<T> {
+    fn default() -> Self {
+        Self {
+            paused_in: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> MessagePoolPlugin<T> {
+    /// Freezes the pools marked with `T` while the app is in `state`, see [`WriteAfterPlugin::pause_in_state`].
+    pub fn pause_in_state<S: States>(mut self, state: S) -> Self {
+        self.paused_in.push(in_state_condition(state));
+        self
+    }
+}
+
+impl<T: 'static> Plugin for MessagePoolPlugin<T> {
     fn build(&self, app: &mut App) {
         app
             .add_message::<MessagePoolEmptied>()
-            .add_systems(Startup, spawn_global_message_pool)
-            .add_systems(Update, process_messages);
+            .add_message::<MessagePoolOverflowed>()
+            .init_resource::<WriteAfterTimeScale>();
+        PausingStates::<T>::add(app, &self.paused_in);
+        add_pool_systems::<T>(app, ProcessingSchedule::of(app));
     }
 }
 
+fn add_pool_systems<T: 'static>(app: &mut App, schedule: InternedScheduleLabel) {
+    #[cfg(feature = "debug_messages")]
+    app.add_message::<MessageScheduled>().add_message::<MessageFired>();
+    app
+        .add_systems(
+            schedule,
+            (sync_pool_configs::<T>, mark_pending_pools::<T>, process_messages::<T>.run_if(pools_running::<T>))
+                .chain()
+                .in_set(WriteAfterSet),
+        )
+        .add_systems(
+            FixedUpdate,
+            (mark_pending_pools::<T>, process_fixed_messages::<T>.run_if(pools_running::<T>))
+                .chain()
+                .in_set(WriteAfterSet),
+        );
+}
+
+/// Systems processing the pools, in [`WriteAfterPlugin::schedule`] and in `FixedUpdate`.
+///
+/// Messages written by a pool can be read by systems ordered `.after(WriteAfterSet)` in the same run.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WriteAfterSet;
+
+/// A scheduled message. Everything but the erased payload is reflected, so inspectors show what
+/// each pending message is, when it fires and how it repeats.
+#[derive(Reflect)]
 struct QueuedMessage {
+    handle: MessageHandle,
+    /// Position in scheduling order.
+    order: u64,
+    type_id: TypeId,
+    /// Type path of the message.
+    type_name: &'static str,
+    /// Label given to [`MessagePool::write_after_labeled`].
+    label: Option<&'static str>,
+    key: Option<Cow<'static, str>>,
+    batch: Option<BatchHandle>,
+    tags: Vec<TagId>,
+    after: Option<MessageHandle>,
+    priority: i32,
     timer: Timer,
-    write_fn: Box<dyn FnOnce(&mut Commands) + Send + Sync + 'static>,
+    /// Elapsed time of the pool at which the timer finishes, while the message is in the deadline queue.
+    deadline: Option<Duration>,
+    until: Option<Until>,
+    due: bool,
+    repeats: Option<u32>,
+    backoff: Option<Backoff>,
+    /// Delay until the predicate of [`MessagePool::write_after_if_or_retry`] is checked again.
+    retry: Option<Duration>,
+    #[cfg(feature = "rand")]
+    jitter: Option<Jitter>,
+    /// Messages rebuilt from reflection write nothing.
+    #[reflect(ignore, default = "write_nothing")]
+    write_fn: WriteFn,
+    #[reflect(ignore)]
+    on_complete: Option<WriteFn>,
+    /// Copy of the message kept for [`MessagePool::snapshot`].
+    #[reflect(ignore)]
+    saved: Option<Box<dyn PartialReflect>>,
+    /// The message shared with `write_fn`, compared by [`MessagePool::write_after_dedup`].
+    #[reflect(ignore)]
+    payload: Option<Arc<dyn Any + Send + Sync>>,
 }
 
-type CustomEmptiedMessage = Box<dyn Fn(&mut Commands, Entity) + Send + Sync + 'static>;
+/// Alternative to the timer deciding when a [`QueuedMessage`] is due.
+#[derive(Clone, Copy, PartialEq, Reflect)]
+enum Until {
+    /// Runs of the processing system left.
+    Frames(u32),
+    /// `FixedUpdate` ticks left.
+    Ticks(u32),
+    /// Elapsed time of the pool's clock to wait for.
+    Elapsed(Duration),
+    /// Wall-clock time to wait for.
+    SystemTime(#[reflect(ignore, default = "unix_epoch")] SystemTime),
+    /// Next elapsed time of the pool's clock matching `offset + n * period`.
+    Aligned {
+        period: Duration,
+        offset: Duration,
+        next: Duration,
+    },
+}
 
-/// Message sent when the pool is empty.
-#[derive(Message)]
-pub struct MessagePoolEmptied(pub Entity);
+impl Until {
+    fn is_due(&self) -> bool {
+        matches!(self, Until::Frames(0) | Until::Ticks(0))
+    }
 
-/// Global message pool.
-#[derive(Component)]
-pub struct GlobalMessagePool;
+    fn aligned(period: Duration, offset: Duration, elapsed: Duration) -> Self {
+        let period = period.max(Duration::from_nanos(1));
+        let next = match elapsed.checked_sub(offset) {
+            Some(since) => offset + Duration::from_nanos(((since.as_nanos() / period.as_nanos() + 1) * period.as_nanos()) as u64),
+            None => offset,
+        };
+        Until::Aligned { period, offset, next }
+    }
+}
+
+/// What a processing run advances.
+struct Step {
+    delta: Duration,
+    /// Elapsed time of the pool's clock, `None` when the run doesn't advance it.
+    elapsed: Option<Duration>,
+    frame: bool,
+    fixed_tick: bool,
+}
+
+/// How a pool created with [`MessagePool::with_ticks`] advances.
+#[derive(Clone, Copy, Reflect)]
+struct TickMode {
+    tick: Duration,
+    /// Whether only [`WriteAfterEntityCommandsExt::advance_ticks`] advances the pool.
+    manual: bool,
+}
+
+impl TickMode {
+    /// Rounds `delay` to the nearest whole tick.
+    fn round(&self, delay: Duration) -> Duration {
+        let tick = self.tick.as_nanos().max(1);
+        let ticks = (delay.as_nanos() + tick / 2) / tick;
+        Duration::from_nanos(u64::try_from(ticks * tick).unwrap_or(u64::MAX))
+    }
+
+    fn step(&self, elapsed: Duration) -> Step {
+        Step {
+            delta: self.tick,
+            elapsed: Some(elapsed + self.tick),
+            frame: true,
+            fixed_tick: true,
+        }
+    }
+}
+
+#[derive(Reflect)]
+struct Backoff {
+    multiplier: f32,
+    max_delay: Duration,
+}
+
+#[cfg(feature = "rand")]
+#[derive(Reflect)]
+struct Jitter {
+    interval: f32,
+    jitter: f32,
+}
+
+#[cfg(feature = "rand")]
+impl Jitter {
+    fn sample(&self, rng: &mut Option<rand::rngs::StdRng>) -> Duration {
+        let offset = random_range(rng, -self.jitter..=self.jitter);
+        Duration::from_secs_f32((self.interval + offset).max(0.0))
+    }
+}
+
+/// Pool and handle of the [`QueuedMessage`] being written.
+#[derive(Clone, Copy)]
+struct Origin {
+    pool: Entity,
+    handle: MessageHandle,
+}
+
+type WriteFn = Box<dyn FnMut(&mut Commands, Origin) + Send + Sync + 'static>;
+
+/// What a pool does when writing a message whose type was never registered with `add_message`,
+/// set with [`WriteAfterPlugin::on_unregistered_message`].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnregisteredMessagePolicy {
+    /// Panics, naming the message type.
+    #[default]
+    Panic,
+    /// Logs a warning naming the message type and drops the message.
+    Warn,
+    /// Registers the message type like `add_message` would, then writes the message.
+    Register,
+}
+
+/// Writes a delayed message, following the [`UnregisteredMessagePolicy`] if its type isn't registered.
+pub(crate) fn write_message<M: Message>(world: &mut World, message: M) {
+    if let Some(mut messages) = world.get_resource_mut::<Messages<M>>() {
+        messages.write(message);
+        return;
+    }
+    let type_name = std::any::type_name::<M>();
+    match world.get_resource::<UnregisteredMessagePolicy>().copied().unwrap_or_default() {
+        UnregisteredMessagePolicy::Panic => {
+            panic!("delayed message {type_name} was written, but its type isn't registered with `add_message`")
+        }
+        UnregisteredMessagePolicy::Warn => {
+            warn!("dropped delayed message {type_name}, its type isn't registered with `add_message`");
+        }
+        UnregisteredMessagePolicy::Register => {
+            MessageRegistry::register_message::<M>(world);
+            world.resource_mut::<Messages<M>>().write(message);
+        }
+    }
+}
 
-#[derive(Component, Default)]
-pub struct MessagePool {
-    messages: Vec<QueuedMessage>,
-    when_emptied: Option<CustomEmptiedMessage>,
+fn write_nothing() -> WriteFn {
+    Box::new(|_, _| {})
 }
 
-impl MessagePool {
-    pub fn write_after<M: Message + Send + Sync + 'static>(&mut self, message: M, delay: f32) {
-        let timer = Timer::from_seconds(delay, TimerMode::Once);
+fn unix_epoch() -> SystemTime {
+    SystemTime::UNIX_EPOCH
+}
+
+fn write_clones<M: Message + Send + Sync + Clone + 'static>(message: M) -> CustomEmptiedMessage {
+    Box::new(move |commands: &mut Commands, _: Entity| {
+        let message = message.clone();
+        commands.queue(move |world: &mut World| {
+            write_message(world, message);
+        });
+    })
+}
 
-        let write_fn = Box::new(move |commands: &mut Commands| {
+fn write_once<M: Message + Send + Sync + 'static>(message: M) -> WriteFn {
+    let mut message = Some(message);
+    Box::new(move |commands: &mut Commands, _: Origin| {
+        if let Some(message) = message.take() {
             commands.queue(move |world: &mut World| {
-                world.resource_mut::<Messages<M>>().write(message);
+                write_message(world, message);
             });
+        }
+    })
+}
+
+impl QueuedMessage {
+    fn new<M: Message + Send + Sync + 'static>(message: M, delay: Duration) -> Self {
+        Self::from_fn::<M>(Timer::new(delay, TimerMode::Once), write_once(message))
+    }
+
+    fn saved<M: Message + Reflect>(message: M, delay: Duration) -> Self {
+        let saved = message.to_dynamic();
+        let mut message = Self::new(message, delay);
+        message.saved = Some(saved);
+        message
+    }
+
+    fn comparable<M: Message + PartialEq>(message: M, delay: Duration) -> Self {
+        let payload = Arc::new(Mutex::new(Some(message)));
+        let shared = payload.clone();
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            if let Some(message) = shared.lock().unwrap().take() {
+                commands.queue(move |world: &mut World| {
+                    write_message(world, message);
+                });
+            }
         });
 
-        self.messages.push(QueuedMessage { timer, write_fn });
+        let mut message = Self::from_fn::<M>(Timer::new(delay, TimerMode::Once), write_fn);
+        message.payload = Some(payload);
+        message
     }
 
-    pub fn write_when_empty<M: Message + Send + Sync + Clone + 'static>(&mut self, message: M) {
-        let write_fn = Box::new(move |commands: &mut Commands, emptied: Entity| {
+    fn is_equal<M: Message + PartialEq>(&self, other: &M) -> bool {
+        self.payload
+            .as_ref()
+            .and_then(|payload| payload.downcast_ref::<Mutex<Option<M>>>())
+            .is_some_and(|payload| payload.lock().unwrap().as_ref() == Some(other))
+    }
+
+    fn repeating<M: Message + Send + Sync + Clone + 'static>(message: M, interval: Duration) -> Self {
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
             let message = message.clone();
             commands.queue(move |world: &mut World| {
-                world.resource_mut::<Messages<MessagePoolEmptied>>().write(MessagePoolEmptied(emptied));
-                world.resource_mut::<Messages<M>>().write(message);
+                write_message(world, message);
             });
         });
 
-        self.when_emptied = Some(write_fn);
+        Self::from_fn::<M>(Timer::new(interval, TimerMode::Repeating), write_fn)
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.messages.is_empty()
+    fn conditional<T: 'static, M, P>(message: M, delay: Duration, predicate: P, retry: Option<Duration>) -> Self
+    where
+        M: Message + Send + Sync + 'static,
+        P: Fn(&World) -> bool + Send + Sync + 'static,
+    {
+        let Some(retry) = retry else {
+            let mut payload = Some((message, predicate));
+            let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+                let Some((message, predicate)) = payload.take() else {
+                    return;
+                };
+                commands.queue(move |world: &mut World| {
+                    if predicate(world) {
+                        write_message(world, message);
+                    }
+                });
+            });
+            return Self::from_fn::<M>(Timer::new(delay, TimerMode::Once), write_fn);
+        };
+
+        // The message stays pending while checked, until the pool is told the predicate held.
+        let payload = Arc::new(Mutex::new(Some(message)));
+        let predicate = Arc::new(predicate);
+        let write_fn = Box::new(move |commands: &mut Commands, origin: Origin| {
+            let payload = payload.clone();
+            let predicate = predicate.clone();
+            commands.queue(move |world: &mut World| {
+                if !predicate(world) {
+                    return;
+                }
+                let Some(message) = payload.lock().unwrap().take() else {
+                    return;
+                };
+                write_message(world, message);
+                if let Some(mut pool) = world.get_mut::<MarkedMessagePool<T>>(origin.pool) {
+                    pool.confirmed.push(origin.handle);
+                }
+            });
+        });
+
+        let mut message = Self::from_fn::<M>(Timer::new(delay, TimerMode::Once), write_fn);
+        message.retry = Some(retry);
+        message
+    }
+
+    /// Whether the timer alone decides when the message is due, so it can wait in the deadline queue.
+    fn is_timed(&self) -> bool {
+        self.until.is_none() && self.after.is_none() && !self.due && !self.timer.is_paused()
+    }
+
+    /// Takes the message out of the deadline queue, bringing its timer up to date.
+    fn take_deadline(&mut self, now: Duration) {
+        if let Some(deadline) = self.deadline.take() {
+            let remaining = deadline.saturating_sub(now);
+            self.timer.set_elapsed(self.timer.duration().saturating_sub(remaining));
+        }
+    }
+
+    fn remaining(&self, now: Duration) -> Duration {
+        self.deadline.map_or(self.timer.remaining(), |deadline| deadline.saturating_sub(now))
+    }
+
+    fn from_fn<M: 'static>(timer: Timer, write_fn: WriteFn) -> Self {
+        Self {
+            handle: MessageHandle(0),
+            order: 0,
+            type_id: TypeId::of::<M>(),
+            type_name: std::any::type_name::<M>(),
+            label: None,
+            key: None,
+            batch: None,
+            tags: Vec::new(),
+            after: None,
+            priority: 0,
+            timer,
+            deadline: None,
+            until: None,
+            due: false,
+            repeats: None,
+            backoff: None,
+            retry: None,
+            #[cfg(feature = "rand")]
+            jitter: None,
+            write_fn,
+            on_complete: None,
+            saved: None,
+            payload: None,
+        }
     }
 }
 
-fn spawn_global_message_pool(
-    mut commands: Commands
-) {
-    commands.spawn((
-        MessagePool::default(),
-        GlobalMessagePool,
-    ));
+type CustomEmptiedMessage = Box<dyn Fn(&mut Commands, Entity) + Send + Sync + 'static>;
+
+type OnceEmptiedMessage = Box<dyn FnOnce(&mut Commands, Entity) + Send + Sync + 'static>;
+
+/// Message sent when the pool is empty.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct MessagePoolEmptied {
+    /// Entity of the emptied pool.
+    pub pool: Entity,
+    /// Number of messages written since the pool last became non-empty.
+    pub delivered: u32,
+    /// How long the pool was non-empty, measured on its clock.
+    pub active_for: Duration,
 }
 
-fn process_messages(
-    mut commands: Commands,
-    time: Res<Time>,
-    query: Query<(Entity, &mut MessagePool)>,
-) {
-    for (entity, mut pool) in query {
-        let mut finished = Vec::new();
+/// Message sent when a message is dropped because its pool was at capacity.
+///
+/// Written the next time the pool is processed, see [`MessagePool::with_capacity`].
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct MessagePoolOverflowed {
+    /// Entity of the full pool.
+    pub pool: Entity,
+    /// The message that was dropped, either the new one or an evicted one depending on the [`OverflowPolicy`].
+    pub dropped: MessageHandle,
+}
 
-        for (i, message) in pool.messages.iter_mut().enumerate() {
-            message.timer.tick(time.delta());
-            if message.timer.is_finished() {
-                finished.push(i);
-            }
+/// Message written for every message scheduled into a pool, with the `debug_messages` feature.
+///
+/// Written the next time the pool is processed. Rejected messages are only reported by
+/// [`MessagePoolOverflowed`].
+#[cfg(feature = "debug_messages")]
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct MessageScheduled {
+    pub pool: Entity,
+    pub handle: MessageHandle,
+    /// Type of the message, see [`PendingMessage::type_name`].
+    pub type_name: &'static str,
+    /// Label given to [`MessagePool::write_after_labeled`].
+    pub label: Option<&'static str>,
+    /// Delay of the timer, zero for messages not driven by one.
+    pub delay: Duration,
+}
+
+/// Message written every time a pool writes a scheduled message, with the `debug_messages` feature.
+#[cfg(feature = "debug_messages")]
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct MessageFired {
+    pub pool: Entity,
+    pub handle: MessageHandle,
+    /// Type of the message, see [`PendingMessage::type_name`].
+    pub type_name: &'static str,
+    /// Label given to [`MessagePool::write_after_labeled`].
+    pub label: Option<&'static str>,
+    /// How late the message was written compared to when it became due, at most one processing step.
+    pub overshoot: Duration,
+}
+
+/// Conversion into a delay, implemented for [`Duration`] and for seconds as `f32` or `f64`.
+pub trait IntoDelay {
+    fn into_delay(self) -> Duration;
+}
+
+impl IntoDelay for Duration {
+    fn into_delay(self) -> Duration {
+        self
+    }
+}
+
+impl IntoDelay for f32 {
+    fn into_delay(self) -> Duration {
+        Duration::from_secs_f32(self)
+    }
+}
+
+impl IntoDelay for f64 {
+    fn into_delay(self) -> Duration {
+        Duration::from_secs_f64(self)
+    }
+}
+
+/// Handle to a message scheduled in a [`MessagePool`].
+///
+/// Handles are unique within the pool that returned them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+#[cfg_attr(feature = "replicon", derive(serde::Serialize, serde::Deserialize))]
+pub struct MessageHandle(u64);
+
+/// When a message scheduled with [`MessagePool::write_recurring`] repeats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Recurrence {
+    /// Every `period`, starting one period from now.
+    Every(Duration),
+    /// Whenever the elapsed time of the pool's [`PoolClock`] reaches `offset + n * period`.
+    ///
+    /// With a `period` of one in-game hour this fires at every full hour of game time.
+    Aligned { period: Duration, offset: Duration },
+}
+
+/// Handle to a group of messages scheduled together with [`MessagePool::write_after_batch`].
+///
+/// Handles are unique within the pool that returned them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub struct BatchHandle(u64);
+
+/// Label attached to scheduled messages with [`MessagePool::tag`], to cancel or count them together.
+///
+/// Implemented for every hashable type, so string literals and user enums both work. Tags of
+/// different types never match, `"ability"` and `String::from("ability")` are different tags.
+pub trait MessageTag: Hash + Send + Sync + 'static {}
+
+impl<T: Hash + Send + Sync + 'static> MessageTag for T {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+struct TagId {
+    type_id: TypeId,
+    hash: u64,
+}
+
+impl TagId {
+    fn of<T: MessageTag>(tag: &T) -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            hash: FixedHasher.hash_one(tag),
         }
+    }
+}
+
+/// What [`MessagePool::write_throttled`] does with messages arriving within the throttle interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum ThrottlePolicy {
+    /// Extra messages are discarded.
+    #[default]
+    Drop,
+    /// Extra messages are queued and written one per interval.
+    Queue,
+}
 
-        for i in finished.into_iter().rev() {
-            let message = pool.messages.remove(i);
-            (message.write_fn)(&mut commands);
-            if let Some(ref when_empty) = pool.when_emptied && pool.messages.is_empty() {
-                (when_empty)(&mut commands, entity);
+/// What a [`MessagePool`] at capacity does with a newly scheduled message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum OverflowPolicy {
+    /// The new message is discarded.
+    #[default]
+    Reject,
+    /// The earliest scheduled message still pending is cancelled to make room.
+    DropOldest,
+    /// The latest scheduled message still pending is cancelled to make room.
+    DropNewest,
+}
+
+/// Marker kept by [`WriteAfterPlugin`] on entities whose [`MessagePool`] has something to process.
+///
+/// Only pools with this marker are processed, so idle pools cost nothing per frame. It is added when
+/// a pool changes while holding messages and removed once the pool is drained, except on the
+/// [`GlobalMessagePool`].
+///
+/// The clock of an idle pool isn't followed, so [`MessagePool::remaining`] of a message scheduled
+/// with [`MessagePool::write_at`] into it is only exact once the pool was processed again.
+///
+/// `T` is the marker of the pool, see [`MarkedMessagePool<T>`].
+#[derive(Component)]
+pub struct HasPendingMessages<T: 'static = ()>(PhantomData<fn() -> T>);
+
+impl<T> Default for HasPendingMessages<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// Marker that freezes every timer of the [`MessagePool`] on the same entity and on its descendants.
+///
+/// Nothing is cancelled; removing the marker lets the pool continue where it stopped.
+#[derive(Component)]
+pub struct PausedPool;
+
+/// Multiplier applied to the delta of the [`MessagePool`] on the same entity and on its descendants.
+///
+/// Scales of ancestors multiply, so a boss slowed to `0.5` with a part at `0.5` runs its part at
+/// `0.25`. Negative values are treated as `0.0`.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct PoolTimeScale(pub f32);
+
+impl Default for PoolTimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Scale of a pool's delta, from [`WriteAfterTimeScale`] and the [`PoolTimeScale`] and [`PausedPool`]
+/// of its entity and ancestors.
+#[derive(SystemParam)]
+pub(crate) struct PoolScale<'w, 's> {
+    global: Res<'w, WriteAfterTimeScale>,
+    parents: Query<'w, 's, &'static ChildOf>,
+    scales: Query<'w, 's, (Has<PausedPool>, Option<&'static PoolTimeScale>)>,
+}
+
+impl PoolScale<'_, '_> {
+    /// Scales `delta` for the pool on `entity`, `None` if the pool or one of its ancestors is paused.
+    pub(crate) fn scale(&self, entity: Entity, delta: Duration) -> Option<Duration> {
+        let mut factor = self.global.0.max(0.0);
+        for entity in std::iter::once(entity).chain(self.parents.iter_ancestors(entity)) {
+            let Ok((paused, scale)) = self.scales.get(entity) else {
+                continue;
+            };
+            if paused {
+                return None;
+            }
+            if let Some(scale) = scale {
+                factor *= scale.0.max(0.0);
             }
         }
-        
+        Some(WriteAfterTimeScale(factor).scale(delta))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::time::Duration;
+/// Clock driving the timers of the [`MessagePool`] on the same entity.
+///
+/// Pools without this component use [`PoolClock::Virtual`].
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[cfg_attr(feature = "replicon", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Component, Default)]
+pub enum PoolClock {
+    /// Game time, `Time<Virtual>`. Stops while the game is paused.
+    #[default]
+    Virtual,
+    /// `Time<Real>`, keeps running while the game is paused. Useful for UI and toasts.
+    Real,
+    /// `Time<Fixed>`. The pool is processed in `FixedUpdate`, once per fixed step.
+    ///
+    /// Messages are written in the first step reaching their delay, even when several steps run in
+    /// one frame, and can be read in that same step after [`WriteAfterSet`]. Delays that are whole
+    /// multiples of the timestep fire exactly on that step.
+    Fixed,
+}
 
-    use bevy::prelude::*;
-    use super::*;
+/// Multiplier applied to the delta of every [`MessagePool`], regardless of its [`PoolClock`].
+///
+/// Combines with the [`PoolTimeScale`] of each pool.
+///
+/// Defaults to `1.0`. Negative values are treated as `0.0`.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct WriteAfterTimeScale(pub f32);
 
-    #[derive(Message, Debug, PartialEq)]
-    struct TestMessage(&'static str);
+impl Default for WriteAfterTimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
 
-    #[test]
-    fn test_message_after() {
-        fn add_message_hello(
-            mut after: Single<&mut MessagePool, Added<MessagePool>>,
-        ) {
-            after.write_after(TestMessage("hello"), 1.0);
-        }
-        
-        fn add_message_hello2(
-            mut after: Single<&mut MessagePool, Added<MessagePool>>,
-        ) {
-            after.write_after(TestMessage("hello2"), 2.0);
+impl WriteAfterTimeScale {
+    fn scale(&self, delta: Duration) -> Duration {
+        // Unscaled deltas stay exact, so fixed steps add up to their delays without drifting.
+        if self.0 == 1.0 {
+            return delta;
         }
+        delta.mul_f32(self.0.max(0.0))
+    }
+}
 
-        let mut app = App::new();
-        app.add_message::<TestMessage>();
-        app.init_resource::<Time>();
-        app.add_plugins(WriteAfterPlugin);
-        app.add_systems(Update, add_message_hello);
-        app.add_systems(Update, add_message_hello2);
-        app.update();
+/// Settings of the [`MessagePool`] on the same entity, inserted along with every pool.
+///
+/// A pool spawned with the default config fills it in from its own settings and [`PoolClock`], so
+/// the settings of every pool can be inspected here. A config that isn't the default, or a change
+/// to the config later on, is applied to the pool instead. Every pool on the entity shares it.
+#[derive(Component, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component, Default)]
+pub struct MessagePoolConfig {
+    /// Pending messages the pool holds at most, see [`MessagePool::with_capacity`].
+    pub capacity: Option<usize>,
+    pub overflow_policy: OverflowPolicy,
+    pub clock: PoolClock,
+    /// Whether to despawn the entity once the pool wrote its last pending message.
+    pub despawn_when_empty: bool,
+    /// What happens to the pending messages when the pool is removed or its entity despawned.
+    pub on_despawn: DespawnPolicy,
+}
 
-        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.5));
-        app.update();
-        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+/// What a [`MessagePool`] does with its pending messages when it is removed, see
+/// [`MessagePoolConfig::on_despawn`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum DespawnPolicy {
+    /// The messages are dropped without being written.
+    #[default]
+    Drop,
+    /// Every message is written right away, once, in the order they would have become due.
+    WriteAll,
+    /// The messages move to the [`GlobalMessagePool`] with the time they had left. Messages of the
+    /// global pool itself are dropped.
+    TransferToGlobal,
+}
 
+impl MessagePoolConfig {
+    fn apply<T: 'static>(&self, pool: &mut MarkedMessagePool<T>) {
+        if pool.capacity != self.capacity {
+            pool.capacity = self.capacity;
+        }
+        if pool.overflow_policy != self.overflow_policy {
+            pool.overflow_policy = self.overflow_policy;
+        }
+    }
+}
 
-        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
-        app.update();
-        assert!(!app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should not be empty");
-        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
-        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+fn init_pool_config<T: 'static>(mut world: DeferredWorld, context: HookContext) {
+    let entity = context.entity;
+    let clock = world.get::<PoolClock>(entity).copied().unwrap_or_default();
+    let Some(config) = world.get::<MessagePoolConfig>(entity).cloned() else {
+        return;
+    };
+    if config == MessagePoolConfig::default() {
+        let Some(pool) = world.get::<MarkedMessagePool<T>>(entity) else {
+            return;
+        };
+        let config = MessagePoolConfig {
+            capacity: pool.capacity,
+            overflow_policy: pool.overflow_policy,
+            clock,
+            ..default()
+        };
+        if config != MessagePoolConfig::default() {
+            *world.get_mut::<MessagePoolConfig>(entity).unwrap() = config;
+        }
+    } else {
+        if let Some(mut pool) = world.get_mut::<MarkedMessagePool<T>>(entity) {
+            config.apply(&mut pool);
+        }
+        if clock != config.clock {
+            world.commands().entity(entity).insert(config.clock);
+        }
+    }
+}
 
-        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
-        app.update();
-        assert!(!app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should not be empty");
-        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello2")]);
+fn release_pending<T: 'static>(mut world: DeferredWorld, context: HookContext) {
+    let entity = context.entity;
+    let policy = world.get::<MessagePoolConfig>(entity).map(|config| config.on_despawn).unwrap_or_default();
+    let global = world.get::<GlobalMessagePool>(entity).is_some();
+    if policy == DespawnPolicy::Drop || (policy == DespawnPolicy::TransferToGlobal && global) {
+        return;
+    }
+    let Some(mut pool) = world.get_mut::<MarkedMessagePool<T>>(entity) else {
+        return;
+    };
+    let pending = pool.take_pending();
+    let elapsed = pool.clock_elapsed;
+    if pending.is_empty() {
+        return;
+    }
+    match policy {
+        DespawnPolicy::Drop => {}
+        DespawnPolicy::WriteAll => write_all(&mut world.commands(), entity, MarkedMessagePool::<T>::in_due_order(pending, elapsed)),
+        DespawnPolicy::TransferToGlobal => {
+            world.commands().queue(move |world: &mut World| {
+                with_global_pool(world, |pool| pool.adopt(pending));
+            });
+        }
     }
 }
 
+/// Writes messages taken from the pool on `entity` right away, in the order given.
+fn write_all(commands: &mut Commands, entity: Entity, pending: Vec<QueuedMessage>) {
+    for mut message in pending {
+        let origin = Origin { pool: entity, handle: message.handle };
+        (message.write_fn)(commands, origin);
+    }
+}
+
+/// Global message pool.
+///
+/// Unlike other pools it is processed even while idle, see [`HasPendingMessages`].
+#[derive(Component)]
+#[require(HasPendingMessages)]
+pub struct GlobalMessagePool;
+
+/// Messages scheduled to be written later.
+///
+/// Messages becoming due during the same processing run are written in the order they became due,
+/// and messages due at the same moment in the order they were scheduled, unless a priority given with
+/// [`MessagePool::write_after_with_priority`] says otherwise. Pools processed in the same run write
+/// their messages one pool after the other, by entity index, whichever thread processed them.
+///
+/// Pools of different subsystems can be told apart at compile time with a [`MarkedMessagePool`].
+pub type MessagePool = MarkedMessagePool<()>;
+
+/// [`MessagePool`] marked with `T`, so `Single<&mut MarkedMessagePool<UiPool>>` finds the UI pool
+/// without a filter. Marked pools need a [`MessagePoolPlugin<T>`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_write_after::{MarkedMessagePool, MessagePoolPlugin};
+/// # #[derive(Message)]
+/// # struct Toast;
+/// struct UiPool;
+///
+/// fn setup(mut commands: Commands) {
+///     commands.spawn(MarkedMessagePool::<UiPool>::default());
+/// }
+///
+/// fn show_toast(mut pool: Single<&mut MarkedMessagePool<UiPool>>) {
+///     pool.write_after(Toast, 2.0);
+/// }
+/// # App::new().add_plugins(MessagePoolPlugin::<UiPool>::default()).add_systems(Update, (setup, show_toast));
+/// ```
+///
+/// The pool implements [`Reflect`] for markers implementing [`TypePath`], so inspectors list every
+/// pending message with its type, timer and repeats. The messages themselves stay opaque.
+/// [`WriteAfterPlugin`] registers [`MessagePool`], marked pools are registered with
+/// `app.register_type::<MarkedMessagePool<T>>()`.
+#[derive(Component, Reflect)]
+#[reflect(Component, Default, from_reflect = false)]
+#[require(MessagePoolConfig)]
+#[component(on_insert = init_pool_config::<T>, on_remove = release_pending::<T>)]
+#[cfg_attr(feature = "rand", component(on_add = seed_from_global_rng::<T>))]
+pub struct MarkedMessagePool<T: 'static> {
+    messages: Slab<QueuedMessage>,
+    /// Deadlines of the timer driven messages, earliest first. Entries no longer matching
+    /// [`QueuedMessage::deadline`] are stale and skipped.
+    #[reflect(ignore)]
+    deadlines: Deadlines,
+    /// Messages looked at on every run: frame, tick or clock based ones, paused ones and ones
+    /// waiting for another message.
+    #[reflect(ignore)]
+    polled: HashSet<MessageHandle>,
+    #[reflect(ignore)]
+    when_emptied: Vec<CustomEmptiedMessage>,
+    /// Like `when_emptied`, dropped once the pool was emptied.
+    #[reflect(ignore)]
+    once_when_emptied: Vec<OnceEmptiedMessage>,
+    /// Messages written when the pending count drops below the threshold they are registered with.
+    #[reflect(ignore)]
+    when_below: Vec<(usize, CustomEmptiedMessage)>,
+    next_id: u64,
+    elapsed: Duration,
+    active_since: Duration,
+    /// Time spent holding pending messages.
+    active_time: Duration,
+    /// Elapsed time of the pool's clock when the pool last wrote a message.
+    last_written: Option<Duration>,
+    delivered: u32,
+    /// Messages written since the last processing system finished with the pool.
+    written: u32,
+    clock_elapsed: Duration,
+    throttles: HashMap<Cow<'static, str>, Duration>,
+    throttle_policy: ThrottlePolicy,
+    capacity: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    overflowed: Vec<MessageHandle>,
+    /// Retried messages whose predicate held, written and left to be removed by the next run.
+    confirmed: Vec<MessageHandle>,
+    /// Whether `clock_elapsed` follows the pool's clock, which stops being the case while the pool is idle.
+    clock_synced: bool,
+    /// Time added to `elapsed` by [`MessagePool::advance`] since the last processing pass, which
+    /// counts it as part of its step.
+    skipped: Duration,
+    ticks: Option<TickMode>,
+    /// Messages scheduled since the last run, reported by [`MessageScheduled`].
+    #[cfg(feature = "debug_messages")]
+    #[reflect(ignore)]
+    scheduled: Vec<MessageScheduled>,
+    #[cfg(feature = "rand")]
+    #[reflect(ignore)]
+    rng: Option<rand::rngs::StdRng>,
+    #[cfg(feature = "timeline")]
+    #[reflect(ignore)]
+    timelines: Vec<timeline::PlayingTimeline>,
+    #[reflect(ignore)]
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for MarkedMessagePool<T> {
+    fn default() -> Self {
+        Self {
+            messages: Slab::default(),
+            deadlines: Deadlines::default(),
+            polled: HashSet::default(),
+            when_emptied: Vec::new(),
+            once_when_emptied: Vec::new(),
+            when_below: Vec::new(),
+            next_id: 0,
+            elapsed: Duration::ZERO,
+            active_since: Duration::ZERO,
+            active_time: Duration::ZERO,
+            last_written: None,
+            delivered: 0,
+            written: 0,
+            clock_elapsed: Duration::ZERO,
+            throttles: HashMap::default(),
+            throttle_policy: ThrottlePolicy::default(),
+            capacity: None,
+            overflow_policy: OverflowPolicy::default(),
+            overflowed: Vec::new(),
+            confirmed: Vec::new(),
+            clock_synced: false,
+            skipped: Duration::ZERO,
+            ticks: None,
+            #[cfg(feature = "debug_messages")]
+            scheduled: Vec::new(),
+            #[cfg(feature = "rand")]
+            rng: None,
+            #[cfg(feature = "timeline")]
+            timelines: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Seeded random source for the randomized delays of every pool spawned while it exists.
+///
+/// Each new [`MessagePool`] without its own seed draws one from this resource, so replays
+/// spawning pools in the same order get the same delays.
+#[cfg(feature = "rand")]
+#[derive(Resource)]
+pub struct WriteAfterRng(pub rand::rngs::StdRng);
+
+#[cfg(feature = "rand")]
+impl WriteAfterRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(rand::SeedableRng::seed_from_u64(seed))
+    }
+}
+
+#[cfg(feature = "rand")]
+fn seed_from_global_rng<T: 'static>(mut world: DeferredWorld, context: HookContext) {
+    let Some(mut global) = world.get_resource_mut::<WriteAfterRng>() else {
+        return;
+    };
+    let rng = rand::SeedableRng::from_rng(&mut global.0);
+    if let Some(mut pool) = world.get_mut::<MarkedMessagePool<T>>(context.entity) && pool.rng.is_none() {
+        pool.rng = Some(rng);
+    }
+}
+
+#[cfg(feature = "rand")]
+fn random_range<T, R>(rng: &mut Option<rand::rngs::StdRng>, range: R) -> T
+where
+    T: rand::distr::uniform::SampleUniform,
+    R: rand::distr::uniform::SampleRange<T>,
+{
+    match rng {
+        Some(rng) => rand::Rng::random_range(rng, range),
+        None => rand::Rng::random_range(&mut rand::rng(), range),
+    }
+}
+
+impl<T: 'static> MarkedMessagePool<T> {
+    /// Creates a pool holding at most `capacity` pending messages.
+    ///
+    /// Scheduling into a full pool applies its [`OverflowPolicy`] and writes a [`MessagePoolOverflowed`].
+    /// Cancelling a message through the policy also cancels the messages waiting for it.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..default()
+        }
+    }
+
+    /// Creates a pool keeping its deadlines in `backend`.
+    pub fn with_backend(backend: PoolBackend) -> Self {
+        Self {
+            deadlines: Deadlines::new(backend),
+            ..default()
+        }
+    }
+
+    /// Creates a pool counting in whole simulation ticks of `tick`, for rollback and lockstep games.
+    ///
+    /// Delays are rounded to the nearest tick when scheduled, and the pool advances by exactly one
+    /// tick per `FixedUpdate` run, regardless of its [`PoolClock`] and time scales. [`PausedPool`]
+    /// still freezes it. Nothing is accumulated from float deltas, so every peer writes the same
+    /// messages on the same tick.
+    pub fn with_ticks(tick: Duration) -> Self {
+        Self {
+            ticks: Some(TickMode { tick, manual: false }),
+            ..default()
+        }
+    }
+
+    /// Same as [`MessagePool::with_ticks`], except the pool only advances through
+    /// [`WriteAfterEntityCommandsExt::advance_ticks`], or [`WriteAfterEntityCommandsExt::advance_marked_ticks`]
+    /// for marked pools, like in a rollback schedule.
+    pub fn with_manual_ticks(tick: Duration) -> Self {
+        Self {
+            ticks: Some(TickMode { tick, manual: true }),
+            ..default()
+        }
+    }
+
+    pub fn write_after<M: Message + Send + Sync + 'static>(&mut self, message: M, delay: impl IntoDelay) -> MessageHandle {
+        self.push(QueuedMessage::new(message, delay.into_delay()))
+    }
+
+    /// Same as [`MessagePool::write_after`], also returning a future resolving once the message
+    /// was written or cancelled, for async tasks awaiting delayed game events.
+    pub fn write_after_notify<M: Message>(&mut self, message: M, delay: impl IntoDelay) -> (MessageHandle, MessageCompletion) {
+        let (sender, completion) = completion::completion();
+        let mut payload = Some((message, sender));
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            if let Some((message, sender)) = payload.take() {
+                commands.queue(move |world: &mut World| {
+                    write_message(world, message);
+                    sender.written();
+                });
+            }
+        });
+        let handle = self.push(QueuedMessage::from_fn::<M>(Timer::new(delay.into_delay(), TimerMode::Once), write_fn));
+        (handle, completion)
+    }
+
+    /// Same as [`MessagePool::write_after`], with a label told apart from other messages of the
+    /// same type in debug messages, traces, [`MessagePool::iter_pending`] and inspectors.
+    pub fn write_after_labeled<M: Message + Send + Sync + 'static>(
+        &mut self,
+        label: &'static str,
+        message: M,
+        delay: impl IntoDelay,
+    ) -> MessageHandle {
+        let mut message = QueuedMessage::new(message, delay.into_delay());
+        message.label = Some(label);
+        self.push(message)
+    }
+
+    /// Writes `message` after `delay`, before lower-priority messages becoming due on the same tick.
+    ///
+    /// Messages scheduled with [`MessagePool::write_after`] have priority `0`.
+    pub fn write_after_with_priority<M: Message + Send + Sync + 'static>(
+        &mut self,
+        message: M,
+        delay: impl IntoDelay,
+        priority: i32,
+    ) -> MessageHandle {
+        let mut message = QueuedMessage::new(message, delay.into_delay());
+        message.priority = priority;
+        self.push(message)
+    }
+
+    /// Schedules every message of `messages` to be written after the same `delay`.
+    ///
+    /// The returned handle cancels the whole group with [`MessagePool::cancel_batch`].
+    pub fn write_after_batch<M: Message + Send + Sync + 'static>(
+        &mut self,
+        messages: impl IntoIterator<Item = M>,
+        delay: impl IntoDelay,
+    ) -> BatchHandle {
+        self.write_staggered(messages, delay, Duration::ZERO)
+    }
+
+    /// Schedules the `i`-th message of `messages` to be written after `start_delay + i * step`.
+    ///
+    /// The returned handle cancels the whole group with [`MessagePool::cancel_batch`].
+    pub fn write_staggered<M: Message + Send + Sync + 'static>(
+        &mut self,
+        messages: impl IntoIterator<Item = M>,
+        start_delay: impl IntoDelay,
+        step: impl IntoDelay,
+    ) -> BatchHandle {
+        let start_delay = start_delay.into_delay();
+        let step = step.into_delay();
+        let batch = BatchHandle(self.next_id());
+        let messages = messages.into_iter();
+        self.messages.reserve(messages.size_hint().0);
+        for (i, message) in messages.enumerate() {
+            let mut message = QueuedMessage::new(message, start_delay + step * i as u32);
+            message.batch = Some(batch);
+            self.push(message);
+        }
+        batch
+    }
+
+    /// Cancels every message scheduled with the given batch handle.
+    ///
+    /// Returns the number of cancelled messages.
+    pub fn cancel_batch(&mut self, batch: BatchHandle) -> usize {
+        self.retain(|message| message.batch != Some(batch))
+    }
+
+    /// Attaches `tag` to a pending message. A message can carry any number of tags.
+    ///
+    /// Returns `false` if the message was already written or cancelled.
+    pub fn tag(&mut self, handle: MessageHandle, tag: impl MessageTag) -> bool {
+        let Some(message) = self.messages.get_mut(handle) else {
+            return false;
+        };
+        let tag = TagId::of(&tag);
+        if !message.tags.contains(&tag) {
+            message.tags.push(tag);
+        }
+        true
+    }
+
+    /// Writes `message` after `delay` with `tag` attached, see [`MessagePool::tag`].
+    pub fn write_after_tagged<M: Message + Send + Sync + 'static>(
+        &mut self,
+        message: M,
+        delay: impl IntoDelay,
+        tag: impl MessageTag,
+    ) -> MessageHandle {
+        let mut message = QueuedMessage::new(message, delay.into_delay());
+        message.tags.push(TagId::of(&tag));
+        self.push(message)
+    }
+
+    /// Cancels every pending message carrying `tag`.
+    ///
+    /// Returns the number of cancelled messages.
+    pub fn cancel_by_tag(&mut self, tag: impl MessageTag) -> usize {
+        let tag = TagId::of(&tag);
+        self.retain(|message| !message.tags.contains(&tag))
+    }
+
+    /// Number of pending messages carrying `tag`.
+    pub fn count_by_tag(&self, tag: impl MessageTag) -> usize {
+        let tag = TagId::of(&tag);
+        self.messages.values().filter(|message| message.tags.contains(&tag)).count()
+    }
+
+    /// Schedules a message whose `delay` only starts once the message behind `parent` is written.
+    ///
+    /// If `parent` is no longer pending, the delay starts right away. Cancelling `parent`
+    /// cancels this message too.
+    pub fn write_after_handle<M: Message + Send + Sync + 'static>(
+        &mut self,
+        parent: MessageHandle,
+        message: M,
+        delay: impl IntoDelay,
+    ) -> MessageHandle {
+        let mut message = QueuedMessage::new(message, delay.into_delay());
+        message.after = Some(parent);
+        self.push(message)
+    }
+
+    /// Writes a message every `interval` until it is cancelled with [`MessagePool::cancel`].
+    pub fn write_every<M: Message + Send + Sync + Clone + 'static>(&mut self, message: M, interval: impl IntoDelay) -> MessageHandle {
+        self.push(QueuedMessage::repeating(message, interval.into_delay()))
+    }
+
+    /// Schedules a message after a delay picked uniformly from `delay` seconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delay` is empty, like `2.0..2.0`.
+    #[cfg(feature = "rand")]
+    pub fn write_after_range<M: Message + Send + Sync + 'static>(
+        &mut self,
+        message: M,
+        delay: impl rand::distr::uniform::SampleRange<f32>,
+    ) -> MessageHandle {
+        assert!(!delay.is_empty(), "write_after_range needs a non-empty range of delays");
+        let delay = random_range(&mut self.rng, delay);
+        self.push(QueuedMessage::new(message, Duration::from_secs_f32(delay)))
+    }
+
+    /// Writes a message repeatedly, every `interval ± jitter`, until it is cancelled.
+    ///
+    /// A new delay is picked for every repetition.
+    #[cfg(feature = "rand")]
+    pub fn write_every_jittered<M: Message + Send + Sync + Clone + 'static>(
+        &mut self,
+        message: M,
+        interval: impl IntoDelay,
+        jitter: impl IntoDelay,
+    ) -> MessageHandle {
+        let jitter = Jitter {
+            interval: interval.into_delay().as_secs_f32(),
+            jitter: jitter.into_delay().as_secs_f32(),
+        };
+        let mut message = QueuedMessage::repeating(message, jitter.sample(&mut self.rng));
+        message.jitter = Some(jitter);
+        self.push(message)
+    }
+
+    /// Seeds the random source used by the randomized delays of this pool.
+    #[cfg(feature = "rand")]
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Some(rand::SeedableRng::seed_from_u64(seed));
+    }
+
+    /// Builder version of [`MessagePool::set_seed`].
+    #[cfg(feature = "rand")]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.set_seed(seed);
+        self
+    }
+
+    /// Writes a message on the given [`Recurrence`] until it is cancelled with [`MessagePool::cancel`].
+    pub fn write_recurring<M: Message + Send + Sync + Clone + 'static>(&mut self, message: M, recurrence: Recurrence) -> MessageHandle {
+        match recurrence {
+            Recurrence::Every(period) => self.write_every(message, period),
+            Recurrence::Aligned { period, offset } => {
+                let mut message = QueuedMessage::repeating(message, Duration::ZERO);
+                message.until = Some(Until::aligned(period, offset, self.clock_elapsed));
+                self.push(message)
+            }
+        }
+    }
+
+    /// Writes a message every `interval`, `count` times in total.
+    pub fn write_repeating<M: Message + Send + Sync + Clone + 'static>(
+        &mut self,
+        message: M,
+        interval: impl IntoDelay,
+        count: u32,
+    ) -> MessageHandle {
+        let mut message = QueuedMessage::repeating(message, interval.into_delay());
+        message.repeats = Some(count);
+        self.push(message)
+    }
+
+    /// Same as [`MessagePool::write_repeating`], writing `completed` together with the last repetition.
+    pub fn write_repeating_then<M, C>(
+        &mut self,
+        message: M,
+        interval: impl IntoDelay,
+        count: u32,
+        completed: C,
+    ) -> MessageHandle
+    where
+        M: Message + Send + Sync + Clone + 'static,
+        C: Message + Send + Sync + 'static,
+    {
+        let mut message = QueuedMessage::repeating(message, interval.into_delay());
+        message.repeats = Some(count);
+        message.on_complete = Some(write_once(completed));
+        self.push(message)
+    }
+
+    /// Writes a message after `initial_delay`, then again with the delay multiplied by
+    /// `multiplier` after every attempt, up to `max_delay` and `max_attempts` writes.
+    ///
+    /// Cancel the returned handle once the message is acknowledged to stop retrying.
+    pub fn write_with_backoff<M: Message + Send + Sync + Clone + 'static>(
+        &mut self,
+        message: M,
+        initial_delay: impl IntoDelay,
+        multiplier: f32,
+        max_delay: impl IntoDelay,
+        max_attempts: u32,
+    ) -> MessageHandle {
+        let mut message = QueuedMessage::repeating(message, initial_delay.into_delay());
+        message.repeats = Some(max_attempts);
+        message.backoff = Some(Backoff {
+            multiplier,
+            max_delay: max_delay.into_delay(),
+        });
+        self.push(message)
+    }
+
+    /// Schedules a message to be written after `frames` runs of the processing system,
+    /// regardless of how much time passes. A message is never written before the next run.
+    pub fn write_after_frames<M: Message + Send + Sync + 'static>(&mut self, message: M, frames: u32) -> MessageHandle {
+        let mut message = QueuedMessage::new(message, Duration::ZERO);
+        message.until = Some(Until::Frames(frames.max(1)));
+        self.push(message)
+    }
+
+    /// Schedules a message to be written after `ticks` runs of `FixedUpdate`, regardless of the
+    /// pool's [`PoolClock`]. A message is never written before the next tick.
+    pub fn write_after_ticks<M: Message + Send + Sync + 'static>(&mut self, message: M, ticks: u32) -> MessageHandle {
+        let mut message = QueuedMessage::new(message, Duration::ZERO);
+        message.until = Some(Until::Ticks(ticks.max(1)));
+        self.push(message)
+    }
+
+    /// Schedules a message to be written once the elapsed time of the pool's [`PoolClock`]
+    /// reaches `target_elapsed`, no matter when it was scheduled.
+    ///
+    /// A target already in the past is written on the next processing run.
+    pub fn write_at<M: Message + Send + Sync + 'static>(&mut self, message: M, target_elapsed: Duration) -> MessageHandle {
+        let mut message = QueuedMessage::new(message, Duration::ZERO);
+        message.until = Some(Until::Elapsed(target_elapsed));
+        self.push(message)
+    }
+
+    /// Schedules a message to be written once the system clock reaches `target`.
+    ///
+    /// The message is written even if the game was paused or closed in the meantime, as long as
+    /// the pool itself is processed. Useful for daily resets and idle mechanics.
+    pub fn write_at_system_time<M: Message + Send + Sync + 'static>(&mut self, message: M, target: SystemTime) -> MessageHandle {
+        let mut message = QueuedMessage::new(message, Duration::ZERO);
+        message.until = Some(Until::SystemTime(target));
+        self.push(message)
+    }
+
+    /// Same as [`MessagePool::write_after`], registering `M` like `add_message` when the message
+    /// is written if that never happened, whatever the [`UnregisteredMessagePolicy`].
+    pub fn write_after_auto<M: Message>(&mut self, message: M, delay: impl IntoDelay) -> MessageHandle {
+        let mut message = Some(message);
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            if let Some(message) = message.take() {
+                commands.queue(move |world: &mut World| {
+                    if !world.contains_resource::<Messages<M>>() {
+                        MessageRegistry::register_message::<M>(world);
+                    }
+                    world.resource_mut::<Messages<M>>().write(message);
+                });
+            }
+        });
+        self.push(QueuedMessage::from_fn::<M>(Timer::new(delay.into_delay(), TimerMode::Once), write_fn))
+    }
+
+    /// Schedules a message built by `make` when the delay completes, rather than when scheduling.
+    pub fn write_after_with<M, F>(&mut self, make: F, delay: impl IntoDelay) -> MessageHandle
+    where
+        M: Message + Send + Sync + 'static,
+        F: FnOnce() -> M + Send + Sync + 'static,
+    {
+        let mut make = Some(make);
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            if let Some(make) = make.take() {
+                let message = make();
+                commands.queue(move |world: &mut World| {
+                    write_message(world, message);
+                });
+            }
+        });
+        self.push(QueuedMessage::from_fn::<M>(Timer::new(delay.into_delay(), TimerMode::Once), write_fn))
+    }
+
+    /// Schedules a message that is only written if `predicate` holds when the delay completes,
+    /// and dropped otherwise.
+    pub fn write_after_if<M, P>(&mut self, message: M, delay: impl IntoDelay, predicate: P) -> MessageHandle
+    where
+        M: Message + Send + Sync + 'static,
+        P: Fn(&World) -> bool + Send + Sync + 'static,
+    {
+        self.push(QueuedMessage::conditional::<T, _, _>(message, delay.into_delay(), predicate, None))
+    }
+
+    /// Same as [`MessagePool::write_after_if`], but checks again every `retry` while `predicate`
+    /// doesn't hold instead of dropping the message. The handle stays valid across retries.
+    ///
+    /// The message stays pending until written, so the pool only counts it as delivered, and can
+    /// only be emptied by it, on its next run after the predicate held.
+    pub fn write_after_if_or_retry<M, P>(
+        &mut self,
+        message: M,
+        delay: impl IntoDelay,
+        predicate: P,
+        retry: impl IntoDelay,
+    ) -> MessageHandle
+    where
+        M: Message + Send + Sync + 'static,
+        P: Fn(&World) -> bool + Send + Sync + 'static,
+    {
+        self.push(QueuedMessage::conditional::<T, _, _>(message, delay.into_delay(), predicate, Some(retry.into_delay())))
+    }
+
+    /// Schedules a message that is silently dropped if `entity` no longer exists when the delay completes.
+    pub fn write_after_scoped<M: Message + Send + Sync + 'static>(
+        &mut self,
+        entity: Entity,
+        message: M,
+        delay: impl IntoDelay,
+    ) -> MessageHandle {
+        self.write_after_if(message, delay, move |world: &World| world.get_entity(entity).is_ok())
+    }
+
+    /// Triggers `event` for its observers once the delay completes.
+    pub fn trigger_after<E>(&mut self, event: E, delay: impl IntoDelay) -> MessageHandle
+    where
+        E: Event,
+        for<'a> E::Trigger<'a>: Default,
+    {
+        let mut event = Some(event);
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            if let Some(event) = event.take() {
+                commands.trigger(event);
+            }
+        });
+        self.push(QueuedMessage::from_fn::<E>(Timer::new(delay.into_delay(), TimerMode::Once), write_fn))
+    }
+
+    /// Triggers the entity event `event` on `target` once the delay completes.
+    ///
+    /// The event is dropped if `target` no longer exists by then.
+    pub fn trigger_targets_after<E>(&mut self, mut event: E, target: Entity, delay: impl IntoDelay) -> MessageHandle
+    where
+        E: EntityEvent,
+        for<'a> E::Trigger<'a>: Default,
+    {
+        *event.event_target_mut() = target;
+        let mut event = Some(event);
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            if let Some(event) = event.take() {
+                commands.queue(move |world: &mut World| {
+                    if world.get_entity(target).is_ok() {
+                        world.trigger(event);
+                    }
+                });
+            }
+        });
+        self.push(QueuedMessage::from_fn::<E>(Timer::new(delay.into_delay(), TimerMode::Once), write_fn))
+    }
+
+    /// Runs the registered one-shot system `system` once the delay completes.
+    pub fn run_system_after(&mut self, system: SystemId, delay: impl IntoDelay) -> MessageHandle {
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            commands.run_system(system);
+        });
+        self.push(QueuedMessage::from_fn::<SystemId>(Timer::new(delay.into_delay(), TimerMode::Once), write_fn))
+    }
+
+    /// Runs the registered one-shot system `system` with `input` once the delay completes.
+    pub fn run_system_with_after<I>(&mut self, system: SystemId<I>, input: I::Inner<'static>, delay: impl IntoDelay) -> MessageHandle
+    where
+        I: SystemInput<Inner<'static>: Send + Sync> + 'static,
+    {
+        let mut input = Some(input);
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            if let Some(input) = input.take() {
+                commands.run_system_with(system, input);
+            }
+        });
+        self.push(QueuedMessage::from_fn::<SystemId<I>>(Timer::new(delay.into_delay(), TimerMode::Once), write_fn))
+    }
+
+    /// Queues `command` once the delay completes.
+    pub fn command_after<C: Command>(&mut self, command: C, delay: impl IntoDelay) -> MessageHandle {
+        let mut command = SyncCell::new(Some(command));
+        let write_fn = Box::new(move |commands: &mut Commands, _: Origin| {
+            if let Some(command) = command.get().take() {
+                commands.queue(command);
+            }
+        });
+        self.push(QueuedMessage::from_fn::<C>(Timer::new(delay.into_delay(), TimerMode::Once), write_fn))
+    }
+
+    /// Runs `f` with exclusive world access once the delay completes.
+    pub fn command_after_fn<F>(&mut self, f: F, delay: impl IntoDelay) -> MessageHandle
+    where
+        F: FnOnce(&mut World) + Send + 'static,
+    {
+        self.command_after(f, delay)
+    }
+
+    /// Runs `f` on the resource `R` once the delay completes. Nothing happens if `R` doesn't exist by then.
+    pub fn update_resource_after<R, F>(&mut self, f: F, delay: impl IntoDelay) -> MessageHandle
+    where
+        R: Resource,
+        F: FnOnce(&mut R) + Send + 'static,
+    {
+        self.command_after_fn(move |world: &mut World| {
+            if let Some(mut resource) = world.get_resource_mut::<R>() {
+                f(&mut resource);
+            }
+        }, delay)
+    }
+
+    /// Sets [`NextState`] to `state` once the delay completes.
+    pub fn set_state_after<S: FreelyMutableState>(&mut self, state: S, delay: impl IntoDelay) -> MessageHandle {
+        self.command_after_fn(move |world: &mut World| {
+            if let Some(mut next_state) = world.get_resource_mut::<NextState<S>>() {
+                next_state.set(state);
+            }
+        }, delay)
+    }
+
+    /// Same as [`MessagePool::set_state_after`], but only if the current state is still `from`
+    /// once the delay completes.
+    pub fn set_state_after_from<S: FreelyMutableState>(&mut self, from: S, state: S, delay: impl IntoDelay) -> MessageHandle {
+        self.command_after_fn(move |world: &mut World| {
+            if world.get_resource::<State<S>>().is_some_and(|current| *current.get() == from)
+                && let Some(mut next_state) = world.get_resource_mut::<NextState<S>>()
+            {
+                next_state.set(state);
+            }
+        }, delay)
+    }
+
+    /// Same as [`MessagePool::write_after`], keeping a reflected copy of the message so
+    /// [`MessagePool::snapshot`] can save it.
+    pub fn write_after_saved<M: Message + Reflect>(&mut self, message: M, delay: impl IntoDelay) -> MessageHandle {
+        self.push(QueuedMessage::saved(message, delay.into_delay()))
+    }
+
+    /// Copies every message scheduled with [`MessagePool::write_after_saved`] together with the
+    /// time it has left, to be scheduled again with [`MessagePool::restore`].
+    ///
+    /// Other messages can't be saved and are left out.
+    pub fn snapshot(&self) -> PoolSnapshot {
+        let mut messages: Vec<_> = self
+            .messages
+            .values()
+            .filter_map(|message| {
+                Some((message.order, SavedMessage {
+                    message: message.saved.as_ref()?.to_dynamic(),
+                    remaining: self.remaining_of(message),
+                    priority: message.priority,
+                    key: message.key.clone(),
+                }))
+            })
+            .collect();
+        messages.sort_by_key(|(order, _)| *order);
+        PoolSnapshot {
+            messages: messages.into_iter().map(|(_, message)| message).collect(),
+        }
+    }
+
+    /// Schedules every message of `snapshot` again after the time it had left.
+    ///
+    /// Message types need [`ReflectDelayedMessage`] in `registry`, messages of other types are
+    /// skipped with a warning.
+    pub fn restore(&mut self, snapshot: &PoolSnapshot, registry: &TypeRegistry) {
+        for saved in &snapshot.messages {
+            let Some(mut message) = ReflectDelayedMessage::schedule(&*saved.message, saved.remaining, registry) else {
+                warn!("can't restore message {}, ReflectDelayedMessage isn't registered", saved.message.reflect_type_path());
+                continue;
+            };
+            message.priority = saved.priority;
+            message.key = saved.key.clone();
+            self.push(message);
+        }
+    }
+
+    /// Schedules a message under `key`, replacing any message already pending under the same key.
+    ///
+    /// Scheduling repeatedly with the same key debounces the message: it is written `delay`
+    /// after the last call.
+    pub fn write_after_keyed<M: Message + Send + Sync + 'static>(
+        &mut self,
+        key: impl Into<Cow<'static, str>>,
+        message: M,
+        delay: impl IntoDelay,
+    ) -> MessageHandle {
+        let key = key.into();
+        self.retain(|message| message.key.as_ref() != Some(&key));
+
+        let mut message = QueuedMessage::new(message, delay.into_delay());
+        message.key = Some(key);
+        self.push(message)
+    }
+
+    /// Schedules a message, replacing any message of type `M` already pending, so the pool holds at
+    /// most one of them. Like [`MessagePool::write_after_keyed`] with the type as key.
+    pub fn write_after_replace<M: Message + Send + Sync + 'static>(&mut self, message: M, delay: impl IntoDelay) -> MessageHandle {
+        self.cancel_all::<M>();
+        self.write_after(message, delay)
+    }
+
+    /// Schedules a message only if no message of type `M` is pending, for cooldowns like one
+    /// pending respawn per player pool.
+    ///
+    /// Returns whether the message was scheduled.
+    pub fn write_after_if_absent<M: Message + Send + Sync + 'static>(&mut self, message: M, delay: impl IntoDelay) -> bool {
+        if self.has_pending::<M>() {
+            return false;
+        }
+        self.write_after(message, delay);
+        true
+    }
+
+    /// Writes `message` after `delay`, unless an equal message scheduled with this method is
+    /// still pending, to not flood with identical notifications.
+    ///
+    /// Returns the handle of the pending message if there is one.
+    pub fn write_after_dedup<M: Message + PartialEq>(&mut self, message: M, delay: impl IntoDelay) -> MessageHandle {
+        match self.find_equal(&message) {
+            Some(handle) => handle,
+            None => self.push(QueuedMessage::comparable(message, delay.into_delay())),
+        }
+    }
+
+    /// Same as [`MessagePool::write_after_dedup`], restarting the pending message instead so it
+    /// is written `delay` from now.
+    pub fn write_after_dedup_refresh<M: Message + PartialEq>(&mut self, message: M, delay: impl IntoDelay) -> MessageHandle {
+        let delay = delay.into_delay();
+        let Some(handle) = self.find_equal(&message) else {
+            return self.push(QueuedMessage::comparable(message, delay));
+        };
+        self.reschedule(handle, delay);
+        handle
+    }
+
+    fn find_equal<M: Message + PartialEq>(&self, message: &M) -> Option<MessageHandle> {
+        self.messages.values().find(|queued| queued.is_equal(message)).map(|queued| queued.handle)
+    }
+
+    /// Writes a message under `key` at most once per `min_interval`.
+    ///
+    /// Messages arriving within the interval are handled according to the pool's
+    /// [`ThrottlePolicy`]. Returns `None` if the message was dropped.
+    pub fn write_throttled<M: Message + Send + Sync + 'static>(
+        &mut self,
+        key: impl Into<Cow<'static, str>>,
+        message: M,
+        min_interval: impl IntoDelay,
+    ) -> Option<MessageHandle> {
+        let key = key.into();
+        let next_allowed = self.throttles.get(&key).copied().unwrap_or_default();
+        let delay = next_allowed.saturating_sub(self.elapsed);
+
+        if !delay.is_zero() && self.throttle_policy == ThrottlePolicy::Drop {
+            return None;
+        }
+
+        self.throttles.insert(key, self.elapsed + delay + min_interval.into_delay());
+        Some(self.push(QueuedMessage::new(message, delay)))
+    }
+
+    /// Sets how [`MessagePool::write_throttled`] handles messages arriving within the interval.
+    pub fn set_throttle_policy(&mut self, policy: ThrottlePolicy) {
+        self.throttle_policy = policy;
+    }
+
+    /// Builder version of [`MessagePool::set_throttle_policy`].
+    pub fn with_throttle_policy(mut self, policy: ThrottlePolicy) -> Self {
+        self.set_throttle_policy(policy);
+        self
+    }
+
+    /// Limits the pool to `capacity` pending messages, `None` for no limit.
+    ///
+    /// Messages already pending beyond the new limit are kept.
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+    }
+
+    /// Sets what scheduling into a full pool does, see [`MessagePool::with_capacity`].
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    /// Builder version of [`MessagePool::set_overflow_policy`].
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.set_overflow_policy(policy);
+        self
+    }
+
+    /// Moves the pending deadlines to `backend`.
+    pub fn set_backend(&mut self, backend: PoolBackend) {
+        let mut deadlines = Deadlines::new(backend);
+        for (deadline, handle) in self.deadlines.drain() {
+            deadlines.push(deadline, handle);
+        }
+        self.deadlines = deadlines;
+    }
+
+    /// Backend keeping the pool's deadlines, see [`MessagePool::with_backend`].
+    pub fn backend(&self) -> PoolBackend {
+        self.deadlines.backend()
+    }
+
+    /// Cancels a scheduled message before it is written.
+    ///
+    /// Returns `false` if the message was already written or cancelled.
+    ///
+    /// Messages waiting for this one, like later steps of a [`MessageSequence`], are cancelled too.
+    pub fn cancel(&mut self, handle: MessageHandle) -> bool {
+        let Some(_message) = self.messages.remove(handle) else {
+            return false;
+        };
+        #[cfg(feature = "trace")]
+        bevy::log::trace!(?handle, type_name = _message.type_name, label = _message.label, "cancelled message");
+        self.polled.remove(&handle);
+
+        let waiting: Vec<_> = self.polled.iter()
+            .copied()
+            .filter(|&waiting| self.messages[waiting].after == Some(handle))
+            .collect();
+        for handle in waiting {
+            self.cancel(handle);
+        }
+        true
+    }
+
+    /// Starts building a sequence of messages, where each step's delay starts once the previous step is written.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_write_after::MessagePool;
+    /// # #[derive(Message)]
+    /// # struct Countdown(u32);
+    /// # let mut pool = MessagePool::default();
+    /// pool.sequence()
+    ///     .then(Countdown(3), 1.0)
+    ///     .then(Countdown(2), 1.0)
+    ///     .then(Countdown(1), 1.0)
+    ///     .schedule();
+    /// ```
+    pub fn sequence(&mut self) -> MessageSequence<'_, T> {
+        MessageSequence { pool: self, steps: Vec::new() }
+    }
+
+    /// Restarts a scheduled message so it is written `delay` from now.
+    ///
+    /// Returns `false` if the message was already written or cancelled, and leaves messages waiting
+    /// for a number of frames or ticks, an elapsed or system time, or an alignment untouched,
+    /// returning `false` too. Cancel and schedule those again instead.
+    pub fn reschedule(&mut self, handle: MessageHandle, delay: impl IntoDelay) -> bool {
+        let elapsed = self.elapsed;
+        let Some(message) = self.get_mut(handle).filter(|message| message.until.is_none()) else {
+            return false;
+        };
+        message.take_deadline(elapsed);
+        message.timer.set_duration(delay.into_delay());
+        message.timer.reset();
+        self.index(handle, elapsed);
+        true
+    }
+
+    /// Writes a scheduled message on the next processing pass regardless of its timer.
+    ///
+    /// Returns `false` if the message was already written or cancelled.
+    pub fn fire_now(&mut self, handle: MessageHandle) -> bool {
+        let elapsed = self.elapsed;
+        let Some(message) = self.get_mut(handle) else {
+            return false;
+        };
+        message.take_deadline(elapsed);
+        message.due = true;
+        self.index(handle, elapsed);
+        true
+    }
+
+    /// Stops the timer of a scheduled message until [`MessagePool::resume`] is called.
+    ///
+    /// Returns `false` if the message was already written or cancelled.
+    pub fn pause(&mut self, handle: MessageHandle) -> bool {
+        let elapsed = self.elapsed;
+        let Some(message) = self.get_mut(handle) else {
+            return false;
+        };
+        message.take_deadline(elapsed);
+        message.timer.pause();
+        self.index(handle, elapsed);
+        true
+    }
+
+    /// Restarts the timer of a message stopped with [`MessagePool::pause`].
+    ///
+    /// Returns `false` if the message was already written or cancelled.
+    pub fn resume(&mut self, handle: MessageHandle) -> bool {
+        let Some(message) = self.get_mut(handle) else {
+            return false;
+        };
+        message.timer.unpause();
+        self.index(handle, self.elapsed);
+        true
+    }
+
+    /// Time left until a scheduled message is written.
+    ///
+    /// Returns `None` if the message was already written or cancelled.
+    pub fn remaining(&self, handle: MessageHandle) -> Option<Duration> {
+        Some(self.remaining_of(self.get(handle)?))
+    }
+
+    fn remaining_of(&self, message: &QueuedMessage) -> Duration {
+        match message.until {
+            Some(Until::Elapsed(target) | Until::Aligned { next: target, .. }) => target.saturating_sub(self.clock_elapsed),
+            Some(Until::SystemTime(target)) => target.duration_since(SystemTime::now()).unwrap_or_default(),
+            _ => message.remaining(self.elapsed),
+        }
+    }
+
+    /// Time until the soonest pending message is written, for countdown displays.
+    ///
+    /// Frame and tick based messages, paused ones and ones waiting for another message are left out.
+    pub fn time_until_next(&self) -> Option<Duration> {
+        self.messages.values().filter_map(|message| self.known_remaining(message)).min()
+    }
+
+    /// Same as [`MessagePool::time_until_next`], only looking at messages of type `M`.
+    pub fn next_of_type<M: Message>(&self) -> Option<Duration> {
+        self.messages
+            .values()
+            .filter(|message| message.type_id == TypeId::of::<M>())
+            .filter_map(|message| self.known_remaining(message))
+            .min()
+    }
+
+    fn known_remaining(&self, message: &QueuedMessage) -> Option<Duration> {
+        let counted = matches!(message.until, Some(Until::Frames(_) | Until::Ticks(_)));
+        if counted || message.after.is_some() || message.timer.is_paused() {
+            return None;
+        }
+        Some(self.remaining_of(message))
+    }
+
+    /// Every pending message with what is known about it, in no particular order.
+    pub fn iter_pending(&self) -> impl Iterator<Item = PendingMessage<'_>> {
+        self.messages.values().map(|message| PendingMessage {
+            handle: message.handle,
+            type_name: message.type_name,
+            label: message.label,
+            remaining: self.remaining_of(message),
+            key: message.key.as_deref(),
+            batch: message.batch,
+            priority: message.priority,
+            paused: message.timer.is_paused(),
+            tags: &message.tags,
+        })
+    }
+
+    /// Runs of the processing system left until a message scheduled with
+    /// [`MessagePool::write_after_frames`] is written.
+    ///
+    /// Returns `None` if the message was already written or cancelled, or is not frame based.
+    pub fn remaining_frames(&self, handle: MessageHandle) -> Option<u32> {
+        match self.get(handle)?.until {
+            Some(Until::Frames(frames)) => Some(frames),
+            _ => None,
+        }
+    }
+
+    /// `FixedUpdate` ticks left until a message scheduled with [`MessagePool::write_after_ticks`] is written.
+    ///
+    /// Returns `None` if the message was already written or cancelled, or is not tick based.
+    pub fn remaining_ticks(&self, handle: MessageHandle) -> Option<u32> {
+        match self.get(handle)?.until {
+            Some(Until::Ticks(ticks)) => Some(ticks),
+            _ => None,
+        }
+    }
+
+    /// Fraction of a scheduled message's delay that has elapsed, from `0.0` to `1.0`.
+    ///
+    /// Returns `None` if the message was already written or cancelled, or if it waits for a number
+    /// of frames or ticks, an elapsed or system time, or an alignment rather than a delay.
+    pub fn progress(&self, handle: MessageHandle) -> Option<f32> {
+        let message = self.get(handle)?;
+        if message.until.is_some() {
+            return None;
+        }
+        let duration = message.timer.duration();
+        if duration.is_zero() {
+            return Some(1.0);
+        }
+        Some(1.0 - message.remaining(self.elapsed).as_secs_f32() / duration.as_secs_f32())
+    }
+
+    /// Marks every scheduled message as due, so all of them are written on the next processing pass.
+    pub fn flush_all(&mut self) {
+        for (handle, message) in self.messages.iter_mut() {
+            message.take_deadline(self.elapsed);
+            message.due = true;
+            self.polled.insert(handle);
+        }
+    }
+
+    /// Fast-forwards every timer of the pool by `delta`, on top of the time passing on the next
+    /// processing pass, which writes what becomes due. Repeating messages are written as many
+    /// times as they would have been.
+    ///
+    /// Frame, tick and clock based messages are not affected, and neither are messages scheduled
+    /// afterwards.
+    pub fn advance(&mut self, delta: impl IntoDelay) {
+        let delta = delta.into_delay();
+        self.elapsed += delta;
+        if !self.messages.is_empty() {
+            self.skipped += delta;
+        }
+    }
+
+    /// Drops every scheduled message without writing it.
+    ///
+    /// Messages registered with [`MessagePool::write_when_empty`] are kept, see
+    /// [`MessagePool::clear_when_empty`].
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.deadlines.clear();
+        self.polled.clear();
+    }
+
+    /// Cancels every scheduled message of type `M`.
+    ///
+    /// Returns the number of cancelled messages.
+    pub fn cancel_all<M: Message>(&mut self) -> usize {
+        self.retain(|message| message.type_id != TypeId::of::<M>())
+    }
+
+    /// Writes `message` every time the pool is emptied by writing its last message.
+    ///
+    /// Every registered message is written, together with a single [`MessagePoolEmptied`].
+    pub fn write_when_empty<M: Message + Send + Sync + Clone + 'static>(&mut self, message: M) {
+        self.when_emptied.push(write_clones(message));
+    }
+
+    /// Writes `message` the next time the pool is emptied, for messages that can't be cloned
+    /// like ones carrying a channel sender.
+    pub fn write_once_when_empty<M: Message + Send + Sync + 'static>(&mut self, message: M) {
+        self.once_when_emptied.push(Box::new(move |commands: &mut Commands, _: Entity| {
+            commands.queue(move |world: &mut World| {
+                write_message(world, message);
+            });
+        }));
+    }
+
+    /// Runs the registered one-shot system `system` every time the pool is emptied, like
+    /// [`MessagePool::write_when_empty`].
+    pub fn run_system_when_empty(&mut self, system: SystemId) {
+        self.when_emptied.push(Box::new(move |commands: &mut Commands, _: Entity| {
+            commands.run_system(system);
+        }));
+    }
+
+    /// Runs `f` with world access the next time the pool is emptied, for reactions depending
+    /// on world state like the score.
+    pub fn run_once_when_empty<F: FnOnce(&mut World) + Send + 'static>(&mut self, f: F) {
+        let f = SyncCell::new(f);
+        self.once_when_emptied.push(Box::new(move |commands: &mut Commands, _: Entity| {
+            commands.queue(SyncCell::to_inner(f));
+        }));
+    }
+
+    /// Removes everything registered with [`MessagePool::write_when_empty`],
+    /// [`MessagePool::write_once_when_empty`], [`MessagePool::run_system_when_empty`] and
+    /// [`MessagePool::run_once_when_empty`].
+    pub fn clear_when_empty(&mut self) {
+        self.when_emptied.clear();
+        self.once_when_emptied.clear();
+    }
+
+    /// Writes `message` every time writing messages drops the number of pending ones below
+    /// `count`, for example to schedule the next wave when fewer than 3 spawns are queued.
+    ///
+    /// Cancelling messages doesn't count.
+    pub fn write_when_below<M: Message + Send + Sync + Clone + 'static>(&mut self, count: usize, message: M) {
+        self.when_below.push((count, write_clones(message)));
+    }
+
+    /// Removes every message registered with [`MessagePool::write_when_below`].
+    pub fn clear_when_below(&mut self) {
+        self.when_below.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Number of messages not written yet.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Time since the pool last went from empty to holding messages, zero while it is empty.
+    pub fn active_for(&self) -> Duration {
+        if self.messages.is_empty() {
+            return Duration::ZERO;
+        }
+        self.elapsed - self.active_since
+    }
+
+    /// Total time the pool spent holding pending messages.
+    pub fn total_active_time(&self) -> Duration {
+        self.active_time
+    }
+
+    /// Time since the pool last wrote a message, for pacing like escalating when nothing was
+    /// written for a while. Returns `None` if it never wrote one.
+    ///
+    /// `time` is the clock of the pool, see [`PoolClock`], so this keeps growing while the pool is idle.
+    pub fn since_last_write<C: Default>(&self, time: &Time<C>) -> Option<Duration> {
+        self.last_written.map(|written| time.elapsed().saturating_sub(written))
+    }
+
+    /// Number of messages of type `M` not written yet.
+    pub fn pending_count<M: Message>(&self) -> usize {
+        self.messages.values().filter(|message| message.type_id == TypeId::of::<M>()).count()
+    }
+
+    /// Whether a message of type `M` is still pending, for example to not schedule a second reminder.
+    pub fn has_pending<M: Message>(&self) -> bool {
+        self.messages.values().any(|message| message.type_id == TypeId::of::<M>())
+    }
+
+    fn tick(&mut self, commands: &mut Commands, entity: Entity, step: Step) {
+        #[cfg(feature = "trace")]
+        let _span = bevy::log::trace_span!("tick_message_pool", pool = %entity, delta = ?step.delta, pending = self.messages.len()).entered();
+        let previous_elapsed = self.elapsed - std::mem::take(&mut self.skipped);
+        let previous_clock_elapsed = self.clock_elapsed;
+        self.elapsed += step.delta;
+        if !self.messages.is_empty() {
+            self.active_time += self.elapsed - previous_elapsed;
+        }
+        if let Some(elapsed) = step.elapsed {
+            self.clock_elapsed = elapsed;
+            if !std::mem::replace(&mut self.clock_synced, true) {
+                // Aligned messages scheduled while idle were aligned to a stale clock.
+                let since = elapsed.saturating_sub(step.delta);
+                for handle in &self.polled {
+                    let message = &mut self.messages[*handle];
+                    if let Some(Until::Aligned { period, offset, .. }) = message.until {
+                        message.until = Some(Until::aligned(period, offset, since));
+                    }
+                }
+            }
+        }
+        let elapsed = self.elapsed;
+        self.throttles.retain(|_, next_allowed| *next_allowed > elapsed);
+
+        #[cfg(feature = "debug_messages")]
+        for mut scheduled in self.scheduled.drain(..) {
+            scheduled.pool = entity;
+            commands.queue(move |world: &mut World| {
+                world.resource_mut::<Messages<MessageScheduled>>().write(scheduled);
+            });
+        }
+
+        for dropped in self.overflowed.drain(..) {
+            let overflowed = MessagePoolOverflowed { pool: entity, dropped };
+            commands.queue(move |world: &mut World| {
+                world.resource_mut::<Messages<MessagePoolOverflowed>>().write(overflowed);
+            });
+        }
+
+        let resolved: Vec<_> = self.polled.iter()
+            .copied()
+            .filter(|&handle| self.messages[handle].after.is_some_and(|after| !self.messages.contains(after)))
+            .collect();
+        for handle in resolved {
+            self.messages[handle].after = None;
+            self.index(handle, previous_elapsed);
+        }
+
+        // (handle, times written, how far into this step it became due, how late it is written)
+        let mut fired = Vec::new();
+
+        let mut due = Vec::new();
+        self.deadlines.pop_due(elapsed, &mut due);
+        for (deadline, handle) in due {
+            let Some(message) = self.messages.get_mut(handle) else {
+                continue;
+            };
+            if message.deadline != Some(deadline) {
+                continue;
+            }
+            message.deadline = None;
+
+            let overshoot = (elapsed - deadline).as_nanos();
+            let duration = message.timer.duration().as_nanos();
+            let times = if message.timer.mode() == TimerMode::Repeating && duration > 0 {
+                message.timer.set_elapsed(Duration::from_nanos((overshoot % duration) as u64));
+                u32::try_from(overshoot / duration).unwrap_or(u32::MAX).saturating_add(1)
+            } else {
+                message.timer.set_elapsed(message.timer.duration());
+                1
+            };
+            fired.push((handle, times, deadline.saturating_sub(previous_elapsed), elapsed - deadline));
+        }
+
+        for &handle in &self.polled {
+            let message = &mut self.messages[handle];
+            let mut due_in = Duration::ZERO;
+            let mut overshoot = Duration::ZERO;
+            if message.after.is_none() {
+                match &mut message.until {
+                    _ if message.timer.is_paused() => {}
+                    Some(Until::Frames(frames)) if step.frame => *frames = frames.saturating_sub(1),
+                    Some(Until::Ticks(ticks)) if step.fixed_tick => *ticks = ticks.saturating_sub(1),
+                    Some(Until::Elapsed(target)) if let Some(elapsed) = step.elapsed && elapsed >= *target => {
+                        due_in = target.saturating_sub(previous_clock_elapsed);
+                        overshoot = elapsed - *target;
+                        message.due = true;
+                    }
+                    Some(Until::Aligned { next, .. }) if let Some(elapsed) = step.elapsed && elapsed >= *next => {
+                        due_in = next.saturating_sub(previous_clock_elapsed);
+                        overshoot = elapsed - *next;
+                        message.due = true;
+                    }
+                    Some(Until::SystemTime(target)) if step.elapsed.is_some() && let Ok(late) = SystemTime::now().duration_since(*target) => {
+                        overshoot = late;
+                        message.due = true;
+                    }
+                    _ => {}
+                }
+            }
+            if message.due || message.until.is_some_and(|until| until.is_due()) {
+                fired.push((handle, 1, due_in, overshoot));
+            }
+        }
+
+        // Priority first, then the moment within the step the message became due, then scheduling order.
+        fired.sort_by_key(|&(handle, _, due_in, _)| {
+            let message = &self.messages[handle];
+            (Reverse(message.priority), due_in, message.order)
+        });
+
+        let mut finished = Vec::new();
+        let confirmed = std::mem::take(&mut self.confirmed);
+        fired.retain(|(handle, ..)| !confirmed.contains(handle));
+        for handle in confirmed {
+            if self.messages.contains(handle) {
+                self.delivered += 1;
+                self.written += 1;
+                self.last_written = Some(self.clock_elapsed);
+                finished.push(handle);
+            }
+        }
+
+        #[cfg_attr(not(any(feature = "debug_messages", feature = "trace")), expect(unused_variables))]
+        for (handle, times, _, overshoot) in fired {
+            let message = &mut self.messages[handle];
+            if let Some(retry) = message.retry {
+                // Not written yet, the predicate is checked by the queued write.
+                (message.write_fn)(commands, Origin { pool: entity, handle: message.handle });
+                message.timer = Timer::new(retry, TimerMode::Once);
+                message.due = false;
+                self.index(handle, elapsed);
+                continue;
+            }
+            let times = if message.backoff.is_some() { 1 } else { times };
+            #[cfg(feature = "rand")]
+            let times = if message.jitter.is_some() { 1 } else { times };
+            let times = message.repeats.map_or(times, |repeats| times.min(repeats));
+            #[cfg(feature = "trace")]
+            bevy::log::trace!(?handle, type_name = message.type_name, label = message.label, times, ?overshoot, "wrote message");
+            for _ in 0..times {
+                (message.write_fn)(commands, Origin { pool: entity, handle: message.handle });
+                #[cfg(feature = "debug_messages")]
+                {
+                    let fired = MessageFired { pool: entity, handle, type_name: message.type_name, label: message.label, overshoot };
+                    commands.queue(move |world: &mut World| {
+                        world.resource_mut::<Messages<MessageFired>>().write(fired);
+                    });
+                }
+            }
+            self.delivered += times;
+            self.written += times;
+            self.last_written = Some(self.clock_elapsed);
+            if let Some(repeats) = &mut message.repeats {
+                *repeats -= times;
+            }
+
+            if message.timer.mode() == TimerMode::Repeating && message.repeats != Some(0) {
+                if let Some(backoff) = &message.backoff {
+                    let delay = message.timer.duration().mul_f32(backoff.multiplier).min(backoff.max_delay);
+                    message.timer.set_duration(delay);
+                }
+                #[cfg(feature = "rand")]
+                if let Some(jitter) = &message.jitter {
+                    message.timer.set_duration(jitter.sample(&mut self.rng));
+                }
+                if let Some(ticks) = self.ticks {
+                    message.timer.set_duration(ticks.round(message.timer.duration()));
+                }
+                if message.due {
+                    message.due = false;
+                    message.timer.reset();
+                }
+                if let Some(Until::Aligned { period, offset, .. }) = message.until {
+                    message.until = Some(Until::aligned(period, offset, self.clock_elapsed));
+                }
+                self.index(handle, elapsed);
+                continue;
+            }
+
+            if let Some(on_complete) = &mut message.on_complete {
+                on_complete(commands, Origin { pool: entity, handle: message.handle });
+            }
+            finished.push(handle);
+        }
+
+        if finished.is_empty() {
+            return;
+        }
+        let pending = self.messages.len();
+        for handle in finished {
+            self.messages.remove(handle);
+            self.polled.remove(&handle);
+        }
+        for (count, when_below) in &self.when_below {
+            if pending >= *count && self.messages.len() < *count {
+                (when_below)(commands, entity);
+            }
+        }
+        #[cfg(feature = "trace")]
+        if self.messages.is_empty() {
+            bevy::log::debug!(pool = %entity, delivered = self.delivered, "message pool emptied");
+        }
+        if !(self.when_emptied.is_empty() && self.once_when_emptied.is_empty()) && self.messages.is_empty() {
+            let emptied = MessagePoolEmptied {
+                pool: entity,
+                delivered: self.delivered,
+                active_for: self.elapsed - self.active_since,
+            };
+            commands.queue(move |world: &mut World| {
+                world.resource_mut::<Messages<MessagePoolEmptied>>().write(emptied);
+            });
+            for when_empty in &self.when_emptied {
+                (when_empty)(commands, entity);
+            }
+            for when_empty in self.once_when_emptied.drain(..) {
+                when_empty(commands, entity);
+            }
+        }
+    }
+
+    /// Whether processing the pool can still do anything.
+    fn needs_processing(&self) -> bool {
+        !self.messages.is_empty() || !self.overflowed.is_empty() || !self.throttles.is_empty()
+    }
+
+    fn get(&self, handle: MessageHandle) -> Option<&QueuedMessage> {
+        self.messages.get(handle)
+    }
+
+    fn get_mut(&mut self, handle: MessageHandle) -> Option<&mut QueuedMessage> {
+        self.messages.get_mut(handle)
+    }
+
+    /// Files a message under the deadline queue if its timer decides when it is due, or under
+    /// the polled messages otherwise. Its timer is counted from `now`.
+    fn index(&mut self, handle: MessageHandle, now: Duration) {
+        let Some(message) = self.messages.get_mut(handle) else {
+            return;
+        };
+        message.take_deadline(now);
+        if message.is_timed() {
+            let deadline = now + message.timer.remaining();
+            message.deadline = Some(deadline);
+            self.deadlines.push(deadline, handle);
+            self.polled.remove(&handle);
+        } else {
+            self.polled.insert(handle);
+        }
+    }
+
+    /// Keeps the messages matching `f`, returning how many were dropped.
+    fn retain(&mut self, mut f: impl FnMut(&QueuedMessage) -> bool) -> usize {
+        let mut removed = HashSet::new();
+        self.messages.retain(|message| {
+            let keep = f(message);
+            if !keep {
+                #[cfg(feature = "trace")]
+                bevy::log::trace!(handle = ?message.handle, type_name = message.type_name, label = message.label, "cancelled message");
+                removed.insert(message.handle);
+            }
+            keep
+        });
+        let messages = &self.messages;
+        self.polled.retain(|&handle| messages.contains(handle));
+
+        // Like with `cancel`, messages waiting for a removed one are cancelled too.
+        let waiting: Vec<_> = self.polled.iter()
+            .copied()
+            .filter(|&waiting| self.messages[waiting].after.is_some_and(|after| removed.contains(&after)))
+            .collect();
+        for handle in waiting {
+            self.cancel(handle);
+        }
+        removed.len()
+    }
+
+    /// Removes every pending message, with its timer brought up to date, in scheduling order.
+    pub(crate) fn take_pending(&mut self) -> Vec<QueuedMessage> {
+        let handles: Vec<_> = self.messages.iter_mut().map(|(handle, _)| handle).collect();
+        let mut pending: Vec<_> = handles.into_iter().filter_map(|handle| self.messages.remove(handle)).collect();
+        for message in &mut pending {
+            message.take_deadline(self.elapsed);
+        }
+        pending.sort_by_key(|message| message.order);
+        self.deadlines.clear();
+        self.polled.clear();
+        pending
+    }
+
+    /// Removes every pending message like [`MarkedMessagePool::take_pending`], in the order they
+    /// would have become due.
+    pub(crate) fn take_due(&mut self) -> Vec<QueuedMessage> {
+        let pending = self.take_pending();
+        Self::in_due_order(pending, self.clock_elapsed)
+    }
+
+    /// Sorts messages taken with [`MarkedMessagePool::take_pending`] by when they would have become
+    /// due, `clock_elapsed` being the pool's clock. Messages counting frames or ticks count as due now.
+    fn in_due_order(mut pending: Vec<QueuedMessage>, clock_elapsed: Duration) -> Vec<QueuedMessage> {
+        let now = SystemTime::now();
+        let mut due_in = HashMap::<MessageHandle, Duration>::new();
+        // In scheduling order, messages come after the ones they wait for.
+        for message in &pending {
+            let own = match message.until {
+                None => message.timer.remaining(),
+                Some(Until::Elapsed(target) | Until::Aligned { next: target, .. }) => target.saturating_sub(clock_elapsed),
+                Some(Until::SystemTime(target)) => target.duration_since(now).unwrap_or_default(),
+                Some(Until::Frames(_) | Until::Ticks(_)) => Duration::ZERO,
+            };
+            let after = message.after.and_then(|after| due_in.get(&after).copied()).unwrap_or_default();
+            due_in.insert(message.handle, after + own);
+        }
+        pending.sort_by_key(|message| (due_in[&message.handle], message.order));
+        pending
+    }
+
+    /// Schedules messages taken from another pool, keeping the links between them.
+    fn adopt(&mut self, messages: Vec<QueuedMessage>) {
+        let mut handles = HashMap::new();
+        let mut batches = HashMap::new();
+        for mut message in messages {
+            let previous = message.handle;
+            message.after = message.after.and_then(|parent| handles.get(&parent).copied());
+            message.batch = message.batch.map(|batch| *batches.entry(batch).or_insert_with(|| BatchHandle(self.next_id())));
+            handles.insert(previous, self.push(message));
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn push(&mut self, mut message: QueuedMessage) -> MessageHandle {
+        message.order = self.next_id();
+        if let Some(ticks) = self.ticks {
+            message.timer.set_duration(ticks.round(message.timer.duration()));
+        }
+
+        if let Some(capacity) = self.capacity && self.messages.len() >= capacity {
+            let evicted = {
+                let pending = self.messages.values().map(|message| (message.order, message.handle));
+                match self.overflow_policy {
+                    _ if capacity == 0 => None,
+                    OverflowPolicy::Reject => None,
+                    OverflowPolicy::DropOldest => pending.min(),
+                    OverflowPolicy::DropNewest => pending.max(),
+                }
+            };
+            let Some((_, evicted)) = evicted else {
+                // Rejected messages still get a handle, one that never matches a pending message.
+                #[cfg(feature = "trace")]
+                bevy::log::debug!(type_name = message.type_name, capacity, "rejected message, pool at capacity");
+                let handle = self.messages.insert_with(|_| message);
+                self.messages.remove(handle);
+                self.overflowed.push(handle);
+                return handle;
+            };
+            self.cancel(evicted);
+            self.overflowed.push(evicted);
+        }
+
+        if self.messages.is_empty() {
+            self.active_since = self.elapsed;
+            self.delivered = 0;
+        }
+        let handle = self.messages.insert_with(|handle| {
+            message.handle = handle;
+            message
+        });
+        self.stored(handle);
+        #[cfg(feature = "trace")]
+        {
+            let message = &self.messages[handle];
+            bevy::log::trace!(?handle, type_name = message.type_name, label = message.label, delay = ?message.timer.duration(), "scheduled message");
+        }
+        #[cfg(feature = "debug_messages")]
+        {
+            let message = &self.messages[handle];
+            let delay = if message.until.is_some() { Duration::ZERO } else { message.timer.duration() };
+            self.scheduled.push(MessageScheduled {
+                pool: Entity::PLACEHOLDER,
+                handle,
+                type_name: message.type_name,
+                label: message.label,
+                delay,
+            });
+        }
+        handle
+    }
+
+    fn stored(&mut self, handle: MessageHandle) {
+        self.index(handle, self.elapsed);
+
+        // Cancelled messages leave their deadlines behind, drop them before they pile up.
+        if self.deadlines.len() > 2 * self.messages.len() + 64 {
+            let messages = &self.messages;
+            self.deadlines.retain(|deadline, handle| {
+                messages.get(handle).is_some_and(|message| message.deadline == Some(deadline))
+            });
+        }
+    }
+}
+
+/// A message pending in a pool, returned by [`MessagePool::iter_pending`].
+#[derive(Clone, Debug)]
+pub struct PendingMessage<'a> {
+    pub handle: MessageHandle,
+    /// Type of the message, or of the event, command or state for the other kinds of scheduling.
+    pub type_name: &'static str,
+    /// Label given to [`MessagePool::write_after_labeled`].
+    pub label: Option<&'static str>,
+    /// Same as [`MessagePool::remaining`].
+    pub remaining: Duration,
+    /// Key given to [`MessagePool::write_after_keyed`] or [`MessagePool::write_throttled`].
+    pub key: Option<&'a str>,
+    pub batch: Option<BatchHandle>,
+    pub priority: i32,
+    /// Whether the message was stopped with [`MessagePool::pause`].
+    pub paused: bool,
+    tags: &'a [TagId],
+}
+
+impl PendingMessage<'_> {
+    /// Whether the message carries `tag`, see [`MessagePool::tag`].
+    pub fn has_tag(&self, tag: impl MessageTag) -> bool {
+        self.tags.contains(&TagId::of(&tag))
+    }
+}
+
+/// Builder returned by [`MessagePool::sequence`].
+pub struct MessageSequence<'a, T: 'static = ()> {
+    pool: &'a mut MarkedMessagePool<T>,
+    steps: Vec<QueuedMessage>,
+}
+
+impl<T: 'static> MessageSequence<'_, T> {
+    /// Adds a step written `delay` after the previous step.
+    pub fn then<M: Message + Send + Sync + 'static>(mut self, message: M, delay: impl IntoDelay) -> Self {
+        self.steps.push(QueuedMessage::new(message, delay.into_delay()));
+        self
+    }
+
+    /// Schedules all steps, returning their handles in order.
+    ///
+    /// Cancelling a step also cancels every step after it.
+    pub fn schedule(self) -> Vec<MessageHandle> {
+        let mut handles: Vec<MessageHandle> = Vec::with_capacity(self.steps.len());
+        for mut step in self.steps {
+            step.after = handles.last().copied();
+            handles.push(self.pool.push(step));
+        }
+        handles
+    }
+}
+
+fn spawn_global_message_pool(
+    mut commands: Commands,
+    global: Query<(), With<GlobalMessagePool>>,
+) {
+    if global.is_empty() {
+        commands.spawn((
+            MessagePool::default(),
+            GlobalMessagePool,
+        ));
+    }
+}
+
+/// Runs `f` on the [`GlobalMessagePool`], spawning it first if it doesn't exist yet.
+fn with_global_pool<T>(world: &mut World, f: impl FnOnce(&mut MessagePool) -> T) -> T {
+    let mut query = world.query_filtered::<Entity, (With<GlobalMessagePool>, With<MessagePool>)>();
+    let entity = match query.iter(world).next() {
+        Some(entity) => entity,
+        None => world.spawn((MessagePool::default(), GlobalMessagePool)).id(),
+    };
+    let mut pool = world.get_mut::<MessagePool>(entity).expect("global pool has a MessagePool");
+    f(&mut pool)
+}
+
+type ConfiguredPool<T> = (Entity, Mut<'static, MarkedMessagePool<T>>, Mut<'static, MessagePoolConfig>, Option<Ref<'static, PoolClock>>);
+
+type ChangedPools<T> = Or<(Changed<MarkedMessagePool<T>>, Changed<MessagePoolConfig>, Changed<PoolClock>)>;
+
+/// Keeps each [`MessagePoolConfig`] and its pool in sync. Whichever changed last wins, the config on a tie.
+fn sync_pool_configs<T: 'static>(
+    mut commands: Commands,
+    ticks: SystemChangeTick,
+    query: Query<ConfiguredPool<T>, ChangedPools<T>>,
+) {
+    let newer = |a: Tick, b: Tick| a.is_newer_than(b, ticks.this_run());
+    for (entity, mut pool, mut config, clock) in query {
+        if !newer(pool.last_changed(), config.last_changed()) {
+            config.apply(&mut pool);
+        } else {
+            let mirrored = MessagePoolConfig {
+                capacity: pool.capacity,
+                overflow_policy: pool.overflow_policy,
+                ..config.clone()
+            };
+            config.set_if_neq(mirrored);
+        }
+
+        let current = clock.as_deref().copied().unwrap_or_default();
+        match clock {
+            Some(clock) if newer(clock.last_changed(), config.last_changed()) => {
+                let mirrored = MessagePoolConfig { clock: *clock, ..config.clone() };
+                config.set_if_neq(mirrored);
+            }
+            _ if current != config.clock => {
+                commands.entity(entity).try_insert(config.clock);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Messages written by every pool since the counter was last taken, kept by [`WriteAfterDiagnosticsPlugin`].
+#[derive(Resource, Default)]
+struct WrittenCounter(std::sync::atomic::AtomicU64);
+
+impl WrittenCounter {
+    fn add(&self, written: u32) {
+        if written > 0 {
+            self.0.fetch_add(written as u64, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    fn take(&self) -> u64 {
+        self.0.swap(0, std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+type IdlePools<T> = (Changed<MarkedMessagePool<T>>, Without<HasPendingMessages<T>>);
+
+type ProcessedPool<T> = (
+    Entity,
+    &'static mut MarkedMessagePool<T>,
+    Option<&'static PoolClock>,
+    Option<&'static MessagePoolConfig>,
+    Has<GlobalMessagePool>,
+);
+
+type ProcessedPools<T> = (With<HasPendingMessages<T>>, Without<PausedPool>);
+
+/// Commands of the pools processed in parallel, applied one pool after the other by entity index
+/// so the order messages are written in doesn't depend on threads.
+#[derive(SystemParam)]
+struct PoolCommands<'w, 's> {
+    entities: &'w Entities,
+    queues: Local<'s, Parallel<Vec<(Entity, CommandQueue)>>>,
+}
+
+impl PoolCommands<'_, '_> {
+    fn scope(&self, pool: Entity, f: impl FnOnce(&mut Commands)) {
+        let mut queue = CommandQueue::default();
+        f(&mut Commands::new_from_entities(&mut queue, self.entities));
+        if !queue.is_empty() {
+            self.queues.borrow_local_mut().push((pool, queue));
+        }
+    }
+
+    fn apply(&mut self, commands: &mut Commands) {
+        let mut queues: Vec<_> = self.queues.drain().collect();
+        queues.sort_unstable_by_key(|(pool, _)| pool.index());
+        for (_, mut queue) in queues {
+            commands.append(&mut queue);
+        }
+    }
+}
+
+fn mark_pending_pools<T: 'static>(
+    mut commands: Commands,
+    query: Query<(Entity, &MarkedMessagePool<T>), IdlePools<T>>,
+) {
+    for (entity, pool) in query {
+        if pool.needs_processing() {
+            commands.entity(entity).try_insert(HasPendingMessages::<T>::default());
+        }
+    }
+}
+
+/// Advances the tick based pool on `entity` by `ticks` ticks, see [`MessagePool::with_ticks`].
+pub(crate) fn advance_pool_ticks<T: 'static>(world: &mut World, entity: Entity, ticks: u32) {
+    let Some(mut pool) = world.get_mut::<MarkedMessagePool<T>>(entity) else {
+        return;
+    };
+    let Some(mode) = pool.ticks else {
+        return;
+    };
+    // Taken out for the duration, so the commands can look at the rest of the world.
+    let mut pool = std::mem::take(&mut *pool);
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, world);
+    for _ in 0..ticks {
+        pool.tick(&mut commands, entity, mode.step(pool.elapsed));
+    }
+    let config = world.get::<MessagePoolConfig>(entity);
+    let global = world.get::<GlobalMessagePool>(entity).is_some();
+    unmark_drained_pool(&mut commands, entity, &mut pool, config, global);
+    if let Some(counter) = world.get_resource::<WrittenCounter>() {
+        counter.add(pool.written);
+    }
+    pool.written = 0;
+    *world.get_mut::<MarkedMessagePool<T>>(entity).unwrap() = pool;
+    queue.apply(world);
+}
+
+/// Removes [`HasPendingMessages`] from a pool that was just drained, or despawns it if its
+/// [`MessagePoolConfig`] says so.
+fn unmark_drained_pool<T: 'static>(
+    commands: &mut Commands,
+    entity: Entity,
+    pool: &mut MarkedMessagePool<T>,
+    config: Option<&MessagePoolConfig>,
+    global: bool,
+) {
+    if !global && !pool.needs_processing() {
+        pool.clock_synced = false;
+        if config.is_some_and(|config| config.despawn_when_empty) {
+            commands.entity(entity).try_despawn();
+        } else {
+            commands.entity(entity).try_remove::<HasPendingMessages<T>>();
+        }
+    }
+}
+
+// Pools are ticked in parallel, each into the command buffer of its thread. Messages of one pool
+// keep their order, messages of different pools are written in no particular order.
+fn process_messages<T: 'static>(
+    mut commands: Commands,
+    mut pool_commands: PoolCommands,
+    time: Res<Time>,
+    real_time: Option<Res<Time<Real>>>,
+    scale: PoolScale,
+    counter: Option<Res<WrittenCounter>>,
+    mut query: Query<ProcessedPool<T>, ProcessedPools<T>>,
+) {
+    query.par_iter_mut().for_each(|(entity, mut pool, clock, config, global)| {
+        let (delta, elapsed) = match clock.copied().unwrap_or_default() {
+            _ if pool.ticks.is_some() => return,
+            PoolClock::Virtual => (time.delta(), time.elapsed()),
+            PoolClock::Real => real_time.as_ref().map(|time| (time.delta(), time.elapsed())).unwrap_or_default(),
+            PoolClock::Fixed => return,
+        };
+        let Some(delta) = scale.scale(entity, delta) else {
+            return;
+        };
+        let step = Step {
+            delta,
+            elapsed: Some(elapsed),
+            frame: true,
+            fixed_tick: false,
+        };
+        pool_commands.scope(entity, |commands| {
+            pool.tick(commands, entity, step);
+            unmark_drained_pool(commands, entity, &mut pool, config, global);
+        });
+        let written = std::mem::take(&mut pool.written);
+        if let Some(counter) = &counter {
+            counter.add(written);
+        }
+    });
+    pool_commands.apply(&mut commands);
+}
+
+fn process_fixed_messages<T: 'static>(
+    mut commands: Commands,
+    mut pool_commands: PoolCommands,
+    time: Res<Time>,
+    scale: PoolScale,
+    counter: Option<Res<WrittenCounter>>,
+    mut query: Query<ProcessedPool<T>, ProcessedPools<T>>,
+) {
+    query.par_iter_mut().for_each(|(entity, mut pool, clock, config, global)| {
+        let Some(delta) = scale.scale(entity, time.delta()) else {
+            return;
+        };
+        let step = if let Some(ticks) = pool.ticks {
+            if ticks.manual {
+                return;
+            }
+            ticks.step(pool.elapsed)
+        } else if clock == Some(&PoolClock::Fixed) {
+            Step {
+                delta,
+                elapsed: Some(time.elapsed()),
+                frame: true,
+                fixed_tick: true,
+            }
+        } else if pool.polled.iter().any(|&handle| matches!(pool.messages[handle].until, Some(Until::Ticks(_)))) {
+            Step {
+                delta: Duration::ZERO,
+                elapsed: None,
+                frame: false,
+                fixed_tick: true,
+            }
+        } else {
+            return;
+        };
+        pool_commands.scope(entity, |commands| {
+            pool.tick(commands, entity, step);
+            unmark_drained_pool(commands, entity, &mut pool, config, global);
+        });
+        let written = std::mem::take(&mut pool.written);
+        if let Some(counter) = &counter {
+            counter.add(written);
+        }
+    });
+    pool_commands.apply(&mut commands);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use bevy::prelude::*;
+    use super::*;
+
+    #[derive(Message, Clone, Debug, PartialEq)]
+    struct TestMessage(&'static str);
+
+    #[derive(Message, Clone, Debug, PartialEq)]
+    struct OtherMessage;
+
+    #[derive(Message, Reflect, Debug, PartialEq)]
+    #[reflect(DelayedMessage)]
+    struct Autosave(u32);
+
+    #[test]
+    fn test_message_after() {
+        fn add_message_hello(
+            mut after: Single<&mut MessagePool, Added<MessagePool>>,
+        ) {
+            after.write_after(TestMessage("hello"), 1.0);
+        }
+        
+        fn add_message_hello2(
+            mut after: Single<&mut MessagePool, Added<MessagePool>>,
+        ) {
+            after.write_after(TestMessage("hello2"), 2.0);
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_systems(Update, add_message_hello);
+        app.add_systems(Update, add_message_hello2);
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.5));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert!(!app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should not be empty");
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert!(!app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should not be empty");
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello2")]);
+    }
+
+    #[test]
+    fn test_cancel() {
+        fn schedule(
+            mut after: Single<&mut MessagePool, Added<MessagePool>>,
+        ) {
+            let handle = after.write_after(TestMessage("cancelled"), 1.0);
+            after.write_after(TestMessage("kept"), 1.0);
+            assert!(after.cancel(handle));
+            assert!(!after.cancel(handle));
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_systems(Update, schedule);
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("kept")]);
+    }
+
+    #[test]
+    fn test_cancel_all() {
+        fn schedule(
+            mut after: Single<&mut MessagePool, Added<MessagePool>>,
+        ) {
+            after.write_after(TestMessage("cancelled"), 1.0);
+            after.write_after(OtherMessage, 1.0);
+            after.write_after(TestMessage("cancelled"), 2.0);
+            assert_eq!(after.cancel_all::<TestMessage>(), 2);
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_message::<OtherMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_systems(Update, schedule);
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(2.0));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+        assert_eq!(app.world_mut().resource_mut::<Messages<OtherMessage>>().drain().collect::<Vec<_>>(), vec![OtherMessage]);
+    }
+
+    #[test]
+    fn test_cancel_all_sequence() {
+        fn schedule(
+            mut after: Single<&mut MessagePool, Added<MessagePool>>,
+        ) {
+            after.sequence()
+                .then(TestMessage("first"), 1.0)
+                .then(OtherMessage, 1.0)
+                .then(TestMessage("third"), 1.0)
+                .schedule();
+            assert_eq!(after.cancel_all::<TestMessage>(), 2);
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_message::<OtherMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_systems(Update, schedule);
+        app.update();
+
+        for _ in 0..4 {
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+            app.update();
+            assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+            assert!(app.world_mut().resource_mut::<Messages<OtherMessage>>().drain().next().is_none());
+        }
+        assert!(app.world_mut().query::<&MessagePool>().iter(app.world()).all(MessagePool::is_empty));
+    }
+
+    #[test]
+    fn test_write_after_keyed() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().write_after_keyed("save", TestMessage("first"), 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.5));
+        app.update();
+        query.single_mut(app.world_mut()).unwrap().write_after_keyed("save", TestMessage("second"), 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.75));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.25));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("second")]);
+    }
+
+    #[test]
+    fn test_write_after_replace() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_message::<OtherMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after(TestMessage("first"), 0.5);
+        pool.write_after(OtherMessage, 0.5);
+        pool.write_after_replace(TestMessage("second"), 1.0);
+        pool.write_after_replace(TestMessage("third"), 1.0);
+        assert_eq!(pool.pending_count::<TestMessage>(), 1);
+        assert_eq!(pool.len(), 2);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("third")]);
+        assert_eq!(app.world_mut().resource_mut::<Messages<OtherMessage>>().drain().count(), 1);
+    }
+
+    #[test]
+    fn test_write_after_if_absent() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        assert!(pool.write_after_if_absent(TestMessage("respawn"), 1.0));
+        assert!(!pool.write_after_if_absent(TestMessage("again"), 0.5));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("respawn")]);
+        assert!(query.single_mut(app.world_mut()).unwrap().write_after_if_absent(TestMessage("again"), 1.0));
+    }
+
+    #[test]
+    fn test_write_after_dedup() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        let first = pool.write_after_dedup(TestMessage("hit"), 1.0);
+        assert_eq!(pool.write_after_dedup(TestMessage("hit"), 0.25), first);
+        pool.write_after_dedup(TestMessage("miss"), 1.0);
+        pool.write_after_dedup_refresh(TestMessage("refreshed"), 1.0);
+        assert_eq!(pool.len(), 3);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.5));
+        app.update();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after_dedup_refresh(TestMessage("refreshed"), 1.0);
+        assert_eq!(pool.len(), 3);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.5));
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("hit"), TestMessage("miss")]
+        );
+
+        // Written messages no longer count as pending.
+        query.single_mut(app.world_mut()).unwrap().write_after_dedup(TestMessage("hit"), 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.5));
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("refreshed")]
+        );
+        assert_eq!(query.single(app.world()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_write_throttled() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        assert!(pool.write_throttled("toast", TestMessage("first"), 1.0).is_some());
+        assert!(pool.write_throttled("toast", TestMessage("dropped"), 1.0).is_none());
+        pool.set_throttle_policy(ThrottlePolicy::Queue);
+        assert!(pool.write_throttled("toast", TestMessage("queued"), 1.0).is_some());
+
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("first")]);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("queued")]);
+    }
+
+    #[test]
+    fn test_reschedule() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let handle = query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("hello"), 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.5));
+        app.update();
+        assert!(query.single_mut(app.world_mut()).unwrap().reschedule(handle, 2.0));
+        assert_eq!(query.single(app.world()).unwrap().remaining(handle), Some(Duration::from_secs(2)));
+        assert_eq!(query.single(app.world()).unwrap().progress(handle), Some(0.0));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+        assert_eq!(query.single(app.world()).unwrap().progress(handle), Some(0.5));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+        assert_eq!(query.single(app.world()).unwrap().remaining(handle), None);
+        assert!(!query.single_mut(app.world_mut()).unwrap().reschedule(handle, 1.0));
+
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        let frames = pool.write_after_frames(TestMessage("frames"), 2);
+        let at = pool.write_at(TestMessage("at"), Duration::from_secs(10));
+        assert!(!pool.reschedule(frames, 5.0));
+        assert!(!pool.reschedule(at, 5.0));
+        assert_eq!(pool.remaining_frames(frames), Some(2));
+        assert_eq!(pool.remaining(at), Some(Duration::from_secs_f32(7.5)));
+    }
+
+    #[test]
+    fn test_fire_now() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        let handle = pool.write_after(TestMessage("hurried"), 10.0);
+        pool.write_after(TestMessage("later"), 10.0);
+        assert!(pool.fire_now(handle));
+
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hurried")]);
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert!(!pool.fire_now(handle));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(10));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("later")]);
+    }
+
+    #[test]
+    fn test_flush_all() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default();
+        pool.write_after(TestMessage("first"), 5.0);
+        pool.write_after(TestMessage("second"), 3.0);
+        let pool = app.world_mut().spawn(pool).id();
+        app.update();
+
+        app.world_mut().get_mut::<MessagePool>(pool).unwrap().flush_all();
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("first"), TestMessage("second")],
+        );
+        assert!(app.world().get::<MessagePool>(pool).unwrap().is_empty());
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(5));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default();
+        pool.write_after(TestMessage("cleared"), 3.0);
+        pool.write_when_empty(TestMessage("emptied"));
+        let pool = app.world_mut().spawn(pool).id();
+        app.update();
+
+        app.world_mut().get_mut::<MessagePool>(pool).unwrap().clear();
+        assert!(app.world().get::<MessagePool>(pool).unwrap().is_empty());
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(5));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+
+        let mut pool = app.world_mut().get_mut::<MessagePool>(pool).unwrap();
+        pool.write_after(TestMessage("kept"), 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("kept"), TestMessage("emptied")]);
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        let handle = pool.write_after(TestMessage("paused"), 1.0);
+        pool.write_after(TestMessage("ticking"), 1.0);
+        assert!(pool.pause(handle));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("ticking")]);
+
+        assert!(query.single_mut(app.world_mut()).unwrap().resume(handle));
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("paused")]);
+    }
+
+    #[test]
+    fn test_paused_pool() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query_filtered::<(Entity, &mut MessagePool), With<GlobalMessagePool>>();
+        let (entity, mut pool) = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after(TestMessage("hello"), 1.0);
+        app.world_mut().entity_mut(entity).insert(PausedPool);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+
+        app.world_mut().entity_mut(entity).remove::<PausedPool>();
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_time_scale() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.insert_resource(WriteAfterTimeScale(10.0));
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("hello"), 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_write_every() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let handle = query.single_mut(app.world_mut()).unwrap().write_every(TestMessage("tick"), 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("tick")]);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(2.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("tick"), TestMessage("tick")]);
+
+        assert!(query.single_mut(app.world_mut()).unwrap().cancel(handle));
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+    }
+
+    #[test]
+    fn test_write_repeating() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_message::<OtherMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().write_repeating_then(TestMessage("tick"), 1.0, 3, OtherMessage);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(2.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("tick"), TestMessage("tick")]);
+        assert!(app.world_mut().resource_mut::<Messages<OtherMessage>>().is_empty(), "should be empty");
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(5.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("tick")]);
+        assert_eq!(app.world_mut().resource_mut::<Messages<OtherMessage>>().drain().collect::<Vec<_>>(), vec![OtherMessage]);
+        assert!(query.single(app.world()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_write_with_backoff() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let handle = query.single_mut(app.world_mut()).unwrap().write_with_backoff(TestMessage("retry"), 1.0, 2.0, 3.0, 4);
+
+        for (delay, remaining) in [(1.0, 2.0), (2.0, 3.0), (3.0, 3.0)] {
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(delay));
+            app.update();
+            assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("retry")]);
+            assert_eq!(query.single(app.world()).unwrap().remaining(handle), Some(Duration::from_secs_f32(remaining)));
+        }
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(3.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("retry")]);
+        assert!(query.single(app.world()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sequence() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().sequence()
+            .then(TestMessage("a"), 1.0)
+            .then(TestMessage("b"), 0.5)
+            .schedule();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("a")]);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.25));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.25));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("b")]);
+    }
+
+    #[test]
+    fn test_write_after_batch() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        let batch = pool.write_after_batch([TestMessage("a"), TestMessage("b")], 1.0);
+        pool.write_after_batch([TestMessage("c")], Duration::from_millis(1000));
+        assert_eq!(pool.cancel_batch(batch), 2);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(1.0));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("c")]);
+    }
+
+    #[test]
+    fn test_write_staggered() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().write_staggered([TestMessage("a"), TestMessage("b"), TestMessage("c")], 1.0, 0.5);
+
+        for expected in ["a", "b", "c"] {
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(if expected == "a" { 1.0 } else { 0.5 }));
+            app.update();
+            assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage(expected)]);
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_seeded_range() {
+        let mut a = MessagePool::default().with_seed(7);
+        let mut b = MessagePool::default().with_seed(7);
+        let handle_a = a.write_after_range(TestMessage("a"), 1.0..2.5);
+        let handle_b = b.write_after_range(TestMessage("b"), 1.0..2.5);
+
+        let delay = a.remaining(handle_a).unwrap();
+        assert_eq!(Some(delay), b.remaining(handle_b));
+        assert!(delay >= Duration::from_secs(1) && delay < Duration::from_secs_f32(2.5));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    #[should_panic(expected = "write_after_range needs a non-empty range of delays")]
+    fn test_empty_range() {
+        MessagePool::default().write_after_range(TestMessage("never"), 2.0..2.0);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_global_rng() {
+        fn delays(seed: u64) -> Vec<Duration> {
+            let mut app = App::new();
+            app.init_resource::<Time>();
+            app.insert_resource(WriteAfterRng::from_seed(seed));
+            app.add_plugins(WriteAfterPlugin::default());
+            (0..3)
+                .map(|_| {
+                    let mut pool = app.world_mut().spawn(MessagePool::default());
+                    let mut pool = pool.get_mut::<MessagePool>().unwrap();
+                    let handle = pool.write_after_range(TestMessage("random"), 1.0..2.0);
+                    pool.remaining(handle).unwrap()
+                })
+                .collect()
+        }
+
+        let delays_a = delays(11);
+        assert_eq!(delays_a, delays(11));
+        assert_ne!(delays_a, delays(12));
+        assert!(delays_a.iter().any(|&delay| delay != delays_a[0]));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_write_every_jittered() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default().with_seed(3);
+        let handle = pool.write_every_jittered(TestMessage("tick"), 1.0, 0.25);
+        let pool = app.world_mut().spawn(pool).id();
+        app.update();
+
+        let mut intervals = Vec::new();
+        for _ in 0..20 {
+            let interval = app.world().get::<MessagePool>(pool).unwrap().remaining(handle).unwrap();
+            assert!(interval >= Duration::from_secs_f32(0.75) && interval <= Duration::from_secs_f32(1.25), "{interval:?}");
+            intervals.push(interval);
+
+            app.world_mut().resource_mut::<Time>().advance_by(interval);
+            app.update();
+            assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("tick")]);
+        }
+        assert!(intervals.iter().any(|&interval| interval != intervals[0]));
+    }
+
+    #[test]
+    fn test_write_after_frames() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let handle = query.single_mut(app.world_mut()).unwrap().write_after_frames(TestMessage("hello"), 2);
+
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+        assert_eq!(query.single(app.world()).unwrap().remaining_frames(handle), Some(1));
+
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_write_after_ticks() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let handle = query.single_mut(app.world_mut()).unwrap().write_after_ticks(TestMessage("hello"), 2);
+
+        app.update();
+        app.world_mut().run_schedule(FixedUpdate);
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+        assert_eq!(query.single(app.world()).unwrap().remaining_ticks(handle), Some(1));
+
+        app.world_mut().run_schedule(FixedUpdate);
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_write_at() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let handle = query.single_mut(app.world_mut()).unwrap().write_at(TestMessage("hello"), Duration::from_secs(3));
+        assert_eq!(query.single(app.world()).unwrap().remaining(handle), Some(Duration::from_secs(2)));
+        assert_eq!(query.single(app.world()).unwrap().progress(handle), None);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_write_at_system_time() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_at_system_time(TestMessage("past"), SystemTime::now() - Duration::from_secs(1));
+        pool.write_at_system_time(TestMessage("future"), SystemTime::now() + Duration::from_secs(3600));
+
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("past")]);
+    }
+
+    #[test]
+    fn test_write_recurring_aligned() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let recurrence = Recurrence::Aligned { period: Duration::from_secs(2), offset: Duration::from_millis(500) };
+        let handle = query.single_mut(app.world_mut()).unwrap().write_recurring(TestMessage("hour"), recurrence);
+        assert_eq!(query.single(app.world()).unwrap().remaining(handle), Some(Duration::from_secs(1) + Duration::from_millis(500)));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(2));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hour")]);
+        assert_eq!(query.single(app.world()).unwrap().remaining(handle), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_write_after_if() {
+        #[derive(Resource)]
+        struct Ready(bool);
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.insert_resource(Ready(false));
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after_if(TestMessage("dropped"), 1.0, |world: &World| world.resource::<Ready>().0);
+        let handle = pool.write_after_if_or_retry(TestMessage("retried"), 1.0, |world: &World| world.resource::<Ready>().0, 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().is_empty(), "should be empty");
+        assert_eq!(query.single(app.world()).unwrap().remaining(handle), Some(Duration::from_secs(1)));
+
+        app.world_mut().resource_mut::<Ready>().0 = true;
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("retried")]);
+    }
+
+    #[test]
+    fn test_retry_when_empty() {
+        #[derive(Resource)]
+        struct Ready(bool);
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_message::<OtherMessage>();
+        app.init_resource::<Time>();
+        app.insert_resource(Ready(false));
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_when_empty(OtherMessage);
+        pool.write_after_if_or_retry(TestMessage("retried"), 1.0, |world: &World| world.resource::<Ready>().0, 1.0);
+
+        for _ in 0..3 {
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+            app.update();
+            assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+            assert!(app.world_mut().resource_mut::<Messages<OtherMessage>>().drain().next().is_none());
+            assert!(app.world_mut().resource_mut::<Messages<MessagePoolEmptied>>().drain().next().is_none());
+            assert!(!query.single(app.world()).unwrap().is_empty());
+        }
+
+        app.world_mut().resource_mut::<Ready>().0 = true;
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("retried")]);
+
+        app.update();
+        assert!(query.single(app.world()).unwrap().is_empty());
+        assert_eq!(app.world_mut().resource_mut::<Messages<OtherMessage>>().drain().collect::<Vec<_>>(), vec![OtherMessage]);
+        let emptied: Vec<_> = app.world_mut().resource_mut::<Messages<MessagePoolEmptied>>().drain().collect();
+        assert_eq!(emptied.len(), 1);
+        assert_eq!(emptied[0].delivered, 1);
+    }
+
+    #[test]
+    fn test_write_after_scoped() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let alive = app.world_mut().spawn_empty().id();
+        let despawned = app.world_mut().spawn_empty().id();
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after_scoped(alive, TestMessage("alive"), 1.0);
+        pool.write_after_scoped(despawned, TestMessage("despawned"), 1.0);
+        app.world_mut().despawn(despawned);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("alive")]);
+    }
+
+    #[test]
+    fn test_write_after_with() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let mut app = App::new();
+        app.add_message::<Autosave>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let slot = Arc::new(AtomicU32::new(1));
+        let made = slot.clone();
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().write_after_with(move || Autosave(made.load(Ordering::Relaxed)), 1.0);
+        slot.store(2, Ordering::Relaxed);
+
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().next().is_none());
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().collect::<Vec<_>>(), vec![Autosave(2)]);
+    }
+
+    #[test]
+    fn test_write_when_empty() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_message::<OtherMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_when_empty(TestMessage("emptied"));
+        pool.write_when_empty(OtherMessage);
+        pool.write_after(TestMessage("hello"), 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello"), TestMessage("emptied")]);
+        assert_eq!(app.world_mut().resource_mut::<Messages<OtherMessage>>().drain().collect::<Vec<_>>(), vec![OtherMessage]);
+        let emptied = app.world_mut().resource_mut::<Messages<MessagePoolEmptied>>().drain().collect::<Vec<_>>();
+        assert_eq!(emptied.len(), 1);
+        assert_eq!(emptied[0].delivered, 1);
+        assert_eq!(emptied[0].active_for, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_write_once_when_empty() {
+        #[derive(Message)]
+        struct Done(std::sync::mpsc::Sender<u32>);
+
+        let mut app = App::new();
+        app.add_message::<Done>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_once_when_empty(Done(sender));
+        pool.command_after_fn(|_: &mut World| {}, 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        let done = app.world_mut().resource_mut::<Messages<Done>>().drain().collect::<Vec<_>>();
+        assert_eq!(done.len(), 1);
+        done[0].0.send(7).unwrap();
+        assert_eq!(receiver.recv().unwrap(), 7);
+
+        query.single_mut(app.world_mut()).unwrap().command_after_fn(|_: &mut World| {}, 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert!(app.world().resource::<Messages<Done>>().is_empty());
+    }
+
+    #[test]
+    fn test_run_when_empty() {
+        #[derive(Resource, Default)]
+        struct Score(u32);
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.init_resource::<Score>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let bonus = app.world_mut().register_system(|mut score: ResMut<Score>| score.0 += 1);
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.run_system_when_empty(bonus);
+        pool.run_once_when_empty(|world: &mut World| {
+            let message = if world.resource::<Score>().0 > 0 { "won" } else { "lost" };
+            world.write_message(TestMessage(message));
+        });
+        pool.write_after(TestMessage("round"), 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world().resource::<Score>().0, 1);
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("round"), TestMessage("won")]
+        );
+
+        query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("round"), 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world().resource::<Score>().0, 2);
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("round")]);
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after(TestMessage("skipped"), 5.0);
+        pool.write_every(TestMessage("tick"), 2.0);
+        pool.write_after(TestMessage("later"), 6.0);
+        pool.advance(Duration::from_secs(5));
+
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("tick"), TestMessage("tick"), TestMessage("skipped")]
+        );
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("tick"), TestMessage("later")]
+        );
+    }
+
+    #[test]
+    fn test_advance_empty_pool() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let pool = app.world_mut().spawn(MessagePool::default()).id();
+        app.update();
+
+        let mut pool = app.world_mut().get_mut::<MessagePool>(pool).unwrap();
+        pool.advance(10.0);
+        pool.write_after(TestMessage("later"), 5.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(100));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(4900));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("later")]);
+    }
+
+    #[test]
+    fn test_activity_stopwatch() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        assert_eq!(query.single(app.world()).unwrap().since_last_write(app.world().resource::<Time>()), None);
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after(TestMessage("first"), 1.0);
+        pool.write_after(TestMessage("second"), 3.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(2));
+        app.update();
+        let pool = query.single(app.world()).unwrap();
+        assert_eq!(pool.active_for(), Duration::from_secs(2));
+        assert_eq!(pool.since_last_write(app.world().resource::<Time>()), Some(Duration::ZERO));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        let pool = query.single(app.world()).unwrap();
+        assert_eq!(pool.active_for(), Duration::ZERO);
+        assert_eq!(pool.total_active_time(), Duration::from_secs(3));
+
+        query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("third"), 2.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        let pool = query.single(app.world()).unwrap();
+        assert_eq!(pool.active_for(), Duration::from_secs(1));
+        assert_eq!(pool.total_active_time(), Duration::from_secs(4));
+        assert_eq!(pool.since_last_write(app.world().resource::<Time>()), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_since_last_write_idle() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("hello"), 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert!(query.single(app.world()).unwrap().is_empty());
+
+        for _ in 0..10 {
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+            app.update();
+        }
+        let pool = query.single(app.world()).unwrap();
+        assert_eq!(pool.since_last_write(app.world().resource::<Time>()), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_write_when_below() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_when_below(2, TestMessage("next wave"));
+        for delay in [1.0, 2.0, 3.0] {
+            pool.write_after(TestMessage("spawn"), delay);
+        }
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("spawn")]);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("spawn"), TestMessage("next wave")]
+        );
+
+        // Still below the threshold, nothing crosses it again.
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("spawn")]);
+    }
+
+    #[test]
+    fn test_trigger_after() {
+        #[derive(Event)]
+        struct TestEvent;
+
+        #[derive(Resource, Default)]
+        struct Triggered(u32);
+
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<Triggered>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_observer(|_: On<TestEvent>, mut triggered: ResMut<Triggered>| triggered.0 += 1);
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().trigger_after(TestEvent, 1.0);
+
+        app.update();
+        assert_eq!(app.world().resource::<Triggered>().0, 0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world().resource::<Triggered>().0, 1);
+    }
+
+    #[test]
+    fn test_trigger_targets_after() {
+        #[derive(EntityEvent)]
+        struct Hit(Entity);
+
+        #[derive(Component, Default)]
+        struct Hits(u32);
+
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let observe = |hit: On<Hit>, mut hits: Query<&mut Hits>| hits.get_mut(hit.0).unwrap().0 += 1;
+        let target = app.world_mut().spawn(Hits::default()).observe(observe).id();
+        let other = app.world_mut().spawn(Hits::default()).observe(observe).id();
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().trigger_targets_after(Hit(other), target, 1.0);
+
+        app.update();
+        assert_eq!(app.world().get::<Hits>(target).unwrap().0, 0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world().get::<Hits>(target).unwrap().0, 1);
+        assert_eq!(app.world().get::<Hits>(other).unwrap().0, 0);
+    }
+
+    #[test]
+    fn test_run_system_after() {
+        #[derive(Resource, Default)]
+        struct Counter(u32);
+
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<Counter>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let increment = app.world_mut().register_system(|mut counter: ResMut<Counter>| counter.0 += 1);
+        let add = app.world_mut().register_system(|In(amount): In<u32>, mut counter: ResMut<Counter>| counter.0 += amount);
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.run_system_after(increment, 1.0);
+        pool.run_system_with_after(add, 10, 1.0);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world().resource::<Counter>().0, 11);
+    }
+
+    #[test]
+    fn test_insert_and_remove_after() {
+        #[derive(Component)]
+        struct Buff;
+
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let entity = app.world_mut().spawn_empty().id();
+        app.world_mut().commands().entity(entity).insert_after(Buff, 1.0);
+        app.world_mut().flush();
+
+        app.update();
+        assert!(!app.world().entity(entity).contains::<Buff>());
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert!(app.world().entity(entity).contains::<Buff>());
+
+        app.world_mut().commands().entity(entity).remove_after::<Buff>(1.0);
+        app.world_mut().flush();
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        app.update();
+        assert!(app.world().entity(entity).contains::<Buff>());
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        app.update();
+        assert!(!app.world().entity(entity).contains::<Buff>());
+    }
+
+    #[test]
+    fn test_update_resource_after() {
+        #[derive(Resource)]
+        struct Score(u32);
+
+        #[derive(Resource)]
+        struct Missing;
+
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.insert_resource(Score(1));
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.update_resource_after(|score: &mut Score| score.0 += 10, 1.0);
+        pool.update_resource_after(|_: &mut Missing| unreachable!(), 1.0);
+
+        app.update();
+        assert_eq!(app.world().resource::<Score>().0, 1);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world().resource::<Score>().0, 11);
+        assert!(query.single(app.world()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_despawn_after() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let entity = app.world_mut().spawn(DespawnAfter::new(1.0).with_message(TestMessage("despawned"))).id();
+        let kept = app.world_mut().spawn(DespawnAfter::new(1.0)).id();
+        app.update();
+        assert!(app.world().get_entity(entity).is_ok());
+        app.world_mut().entity_mut(kept).remove::<DespawnAfter>();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert!(app.world().get_entity(entity).is_err());
+        assert!(app.world().get_entity(kept).is_ok());
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("despawned")]);
+    }
+
+    #[test]
+    fn test_set_state_after() {
+        #[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        enum GameState {
+            #[default]
+            Playing,
+            Menu,
+            GameOver,
+        }
+
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins((bevy::state::app::StatesPlugin, WriteAfterPlugin::default()));
+        app.init_state::<GameState>();
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.set_state_after_from(GameState::Playing, GameState::GameOver, 1.0);
+        pool.set_state_after(GameState::Menu, 0.5);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        app.update();
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::ZERO);
+        app.update();
+        assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Menu);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        app.update();
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::ZERO);
+        app.update();
+        assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Menu);
+    }
+
+    #[test]
+    fn test_write_message_after() {
+        fn schedule(mut commands: Commands) {
+            commands.write_message_after(TestMessage("hello"), 1.0);
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_systems(Startup, schedule);
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+        assert_eq!(app.world_mut().query::<&GlobalMessagePool>().iter(app.world()).count(), 1);
+    }
+
+    #[test]
+    fn test_write_after_param() {
+        fn schedule(mut write_after: WriteAfter) {
+            let handle = write_after.write_after(TestMessage("cancelled"), 1.0);
+            write_after.cancel(handle);
+            write_after.write_after(TestMessage("hello"), 1.0);
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_systems(Startup, schedule);
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_delayed_message_writer() {
+        fn schedule(mut writer: MessageWriter<TestMessage>, mut delayed: DelayedMessageWriter<TestMessage>) {
+            delayed.write_after(TestMessage("later"), 1.0);
+            let handle = delayed.write_after(TestMessage("cancelled"), 1.0);
+            assert!(delayed.cancel(handle));
+            assert!(!delayed.cancel(handle));
+            writer.write(TestMessage("now"));
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins((WriteAfterPlugin::default(), TypedMessagePoolPlugin::<TestMessage>::default()));
+        app.add_systems(Startup, schedule);
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("now")]);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("later")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "requires TypedMessagePoolPlugin::<TestMessage>")]
+    fn test_delayed_message_writer_without_plugin() {
+        fn schedule(mut delayed: DelayedMessageWriter<TestMessage>) {
+            delayed.write_after(TestMessage("later"), 1.0);
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_systems(Startup, schedule);
+        app.update();
+    }
+
+    #[test]
+    fn test_entity_with_pool() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let entity = app.world_mut().spawn_empty().id();
+        app.world_mut().commands().entity(entity).with_pool().write_after(TestMessage("first"), 1.0).write_after(TestMessage("second"), 2.0);
+        app.update();
+        assert!(app.world().get::<MessagePool>(entity).is_some());
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("first")]);
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("second")]);
+    }
+
+    #[test]
+    fn test_capacity() {
+        for (policy, expected) in [
+            (OverflowPolicy::Reject, vec![TestMessage("a"), TestMessage("b")]),
+            (OverflowPolicy::DropOldest, vec![TestMessage("b"), TestMessage("c")]),
+            (OverflowPolicy::DropNewest, vec![TestMessage("a"), TestMessage("c")]),
+        ] {
+            let mut app = App::new();
+            app.add_message::<TestMessage>();
+            app.init_resource::<Time>();
+            app.add_plugins(WriteAfterPlugin::default());
+            let mut pool = MessagePool::with_capacity(2).with_overflow_policy(policy);
+            let a = pool.write_after(TestMessage("a"), 1.0);
+            let b = pool.write_after(TestMessage("b"), 1.0);
+            let c = pool.write_after(TestMessage("c"), 1.0);
+            let pool = app.world_mut().spawn(pool).id();
+
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+            app.update();
+            let mut messages = app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>();
+            messages.sort_by_key(|message| message.0);
+            assert_eq!(messages, expected);
+
+            let dropped = match policy {
+                OverflowPolicy::Reject => c,
+                OverflowPolicy::DropOldest => a,
+                OverflowPolicy::DropNewest => b,
+            };
+            let overflowed = app.world_mut().resource_mut::<Messages<MessagePoolOverflowed>>().drain().collect::<Vec<_>>();
+            assert_eq!(overflowed, vec![MessagePoolOverflowed { pool, dropped }]);
+        }
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default();
+        pool.write_after_with_priority(TestMessage("low"), 1.0, -1);
+        pool.write_after(TestMessage("default"), 1.0);
+        pool.write_after_with_priority(TestMessage("high"), 1.0, 10);
+        app.world_mut().spawn(pool);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("high"), TestMessage("default"), TestMessage("low")],
+        );
+    }
+
+    #[test]
+    fn test_fifo_order() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default();
+        pool.write_after(TestMessage("a"), 1.0);
+        pool.write_after(TestMessage("b"), 1.0);
+        pool.write_after(TestMessage("later"), 0.75);
+        pool.write_after(TestMessage("earlier"), 0.5);
+        pool.write_after(TestMessage("c"), 1.0);
+        app.world_mut().spawn(pool);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("earlier"), TestMessage("later"), TestMessage("a"), TestMessage("b"), TestMessage("c")],
+        );
+    }
+
+    #[test]
+    fn test_many_pending() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default();
+        let handles: Vec<_> = (1..=1000).map(|i| pool.write_after(TestMessage("tick"), Duration::from_millis(i))).collect();
+        for &handle in &handles[..500] {
+            pool.cancel(handle);
+        }
+        pool.reschedule(handles[999], Duration::from_millis(1));
+        let pool = app.world_mut().spawn(pool).id();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(750));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().count(), 251);
+
+        let pool = app.world().get::<MessagePool>(pool).unwrap();
+        assert_eq!(pool.remaining(handles[899]), Some(Duration::from_millis(150)));
+        assert_eq!(pool.progress(handles[999]), None);
+        assert!((pool.progress(handles[799]).unwrap() - 0.9375).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_timer_wheel_backend() {
+        let delays = [0, 3, 16, 17, 64, 100, 1_000, 4_095, 4_096, 70_000, 3_600_000];
+        let mut written = Vec::new();
+        for backend in [PoolBackend::Heap, PoolBackend::TimerWheel { resolution: Duration::from_millis(1) }] {
+            let mut app = App::new();
+            app.add_message::<TestMessage>();
+            app.init_resource::<Time>();
+            app.add_plugins(WriteAfterPlugin::default());
+            let mut pool = MessagePool::with_backend(backend);
+            let handles: Vec<_> = delays.iter().map(|&delay| pool.write_after(TestMessage("tick"), Duration::from_millis(delay))).collect();
+            pool.cancel(handles[5]);
+            pool.write_every(TestMessage("every"), Duration::from_millis(250));
+            app.world_mut().spawn(pool);
+
+            let mut backend_written = Vec::new();
+            for step in [1, 15, 1, 50, 500, 5_000, 100_000, 5_000_000] {
+                app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(step));
+                app.update();
+                backend_written.push(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>());
+            }
+            written.push(backend_written);
+        }
+        assert_eq!(written[0], written[1]);
+        assert_eq!(written[0].iter().flatten().filter(|message| message.0 == "tick").count(), delays.len() - 1);
+    }
+
+    #[test]
+    fn test_has_pending_messages() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let pool = app.world_mut().spawn(MessagePool::default()).id();
+        app.update();
+        assert!(!app.world().entity(pool).contains::<HasPendingMessages>());
+
+        app.world_mut().get_mut::<MessagePool>(pool).unwrap().write_after(TestMessage("hello"), 1.0);
+        app.update();
+        assert!(app.world().entity(pool).contains::<HasPendingMessages>());
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+        assert!(!app.world().entity(pool).contains::<HasPendingMessages>());
+    }
+
+    #[test]
+    fn test_parallel_pools() {
+        #[derive(Message, Debug, PartialEq)]
+        struct Numbered(usize);
+
+        let mut app = App::new();
+        app.add_message::<Numbered>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        for i in 0..500 {
+            let mut pool = MessagePool::default();
+            pool.write_after(Numbered(2 * i), 1.0);
+            pool.write_after(Numbered(2 * i + 1), 1.0);
+            app.world_mut().spawn(pool);
+        }
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        let messages = app.world_mut().resource_mut::<Messages<Numbered>>().drain().collect::<Vec<_>>();
+        assert_eq!(messages, (0..1000).map(Numbered).collect::<Vec<_>>());
+        assert_eq!(app.world_mut().query::<&HasPendingMessages>().iter(app.world()).count(), 1);
+    }
+
+    #[test]
+    fn test_typed_message_pool() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(TypedMessagePoolPlugin::<TestMessage>::default());
+        let mut pool = TypedMessagePool::default();
+        pool.write_after(TestMessage("b"), 1.0);
+        pool.write_after(TestMessage("later"), 2.0);
+        pool.write_after(TestMessage("a"), 0.5);
+        pool.write_after(TestMessage("c"), 1.0);
+        let pool = app.world_mut().spawn(pool).id();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("a"), TestMessage("b"), TestMessage("c")],
+        );
+        assert_eq!(app.world().get::<TypedMessagePool<TestMessage>>(pool).unwrap().len(), 1);
+
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("later")]);
+    }
+
+    #[test]
+    fn test_typed_handles() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins((WriteAfterPlugin::default(), TypedMessagePoolPlugin::<TestMessage>::default()));
+        app.update();
+
+        let mut query = app.world_mut().query_filtered::<(&mut MessagePool, &mut TypedMessagePool<TestMessage>), With<GlobalMessagePool>>();
+        let (mut pool, mut typed) = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after(TestMessage("pooled"), 1.0);
+        let handle = typed.write_after(TestMessage("typed"), 1.0);
+        assert!(typed.cancel(handle));
+        assert!(!typed.cancel(handle));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("pooled")]);
+    }
+
+    #[test]
+    fn test_reused_slot_handles() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default();
+        let cancelled = pool.write_after(TestMessage("cancelled"), 1.0);
+        assert!(pool.cancel(cancelled));
+        let reused = pool.write_after(TestMessage("reused"), 1.0);
+        assert_ne!(cancelled, reused);
+        assert!(!pool.cancel(cancelled));
+        assert_eq!(pool.remaining(cancelled), None);
+        app.world_mut().spawn(pool);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("reused")]);
+    }
+
+    #[test]
+    fn test_in_schedule() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::in_schedule(PostUpdate));
+        app.add_systems(Update, |mut pool: Single<&mut MessagePool>| {
+            if pool.is_empty() {
+                pool.write_after(TestMessage("hello"), 0.0);
+            }
+        });
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_write_after_set() {
+        #[derive(Resource, Default)]
+        struct Received(Vec<TestMessage>);
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.init_resource::<Received>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_systems(Update, (|mut reader: MessageReader<TestMessage>, mut received: ResMut<Received>| {
+            received.0.extend(reader.read().cloned());
+        }).after(WriteAfterSet));
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("hello"), 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world().resource::<Received>().0, vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_early() {
+        #[derive(Resource, Default)]
+        struct Received(Vec<TestMessage>);
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.init_resource::<Received>();
+        app.add_plugins(WriteAfterPlugin::early());
+        app.add_systems(Update, |mut reader: MessageReader<TestMessage>, mut received: ResMut<Received>| {
+            received.0.extend(reader.read().cloned());
+        });
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("hello"), 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world().resource::<Received>().0, vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_early_plugins() {
+        #[derive(Resource, Default)]
+        struct Received(Vec<TestMessage>);
+        struct UiPool;
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.init_resource::<Received>();
+        app.add_plugins((
+            WriteAfterPlugin::early(),
+            TypedMessagePoolPlugin::<TestMessage>::default(),
+            MessagePoolPlugin::<UiPool>::default(),
+        ));
+        app.world_mut().spawn(MarkedMessagePool::<UiPool>::default());
+        app.add_systems(Update, |mut reader: MessageReader<TestMessage>, mut received: ResMut<Received>| {
+            received.0.extend(reader.read().cloned());
+        });
+        app.update();
+
+        let mut query = app.world_mut().query_filtered::<&mut TypedMessagePool<TestMessage>, With<GlobalMessagePool>>();
+        query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("typed"), 1.0);
+        let mut query = app.world_mut().query::<&mut MarkedMessagePool<UiPool>>();
+        query.single_mut(app.world_mut()).unwrap().write_after(TestMessage("ui"), 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        let mut received = std::mem::take(&mut app.world_mut().resource_mut::<Received>().0);
+        received.sort_by_key(|message| message.0);
+        assert_eq!(received, vec![TestMessage("typed"), TestMessage("ui")]);
+    }
+
+    #[test]
+    fn test_fixed_steps() {
+        #[derive(Resource, Default)]
+        struct Received(Vec<Duration>);
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Received>();
+        app.add_plugins((bevy::time::TimePlugin, WriteAfterPlugin::default()));
+        app.insert_resource(Time::<Fixed>::from_duration(Duration::from_micros(15_625)));
+        app.insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)));
+        app.add_systems(FixedUpdate, (|time: Res<Time>, mut reader: MessageReader<TestMessage>, mut received: ResMut<Received>| {
+            received.0.extend(reader.read().map(|_| time.elapsed()));
+        }).after(WriteAfterSet));
+
+        let mut pool = MessagePool::default();
+        pool.write_after(TestMessage("tenth step"), Duration::from_micros(15_625) * 10);
+        pool.write_after(TestMessage("hundredth step"), 1.5625);
+        app.world_mut().spawn((pool, PoolClock::Fixed));
+
+        for _ in 0..40 {
+            app.update();
+        }
+        assert_eq!(app.world().resource::<Received>().0, vec![Duration::from_micros(156_250), Duration::from_micros(1_562_500)]);
+    }
+
+    #[test]
+    fn test_without_global_pool() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default().without_global_pool());
+        app.update();
+        assert_eq!(app.world_mut().query::<&GlobalMessagePool>().iter(app.world()).count(), 0);
+
+        app.world_mut().commands().write_message_after(TestMessage("hello"), 1.0);
+        app.update();
+        assert_eq!(app.world_mut().query::<&GlobalMessagePool>().iter(app.world()).count(), 1);
+    }
+
+    #[test]
+    fn test_plugin_struct_update() {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin {
+            spawn_global_pool: false,
+            ..default()
+        });
+        app.update();
+        assert_eq!(app.world_mut().query::<&GlobalMessagePool>().iter(app.world()).count(), 0);
+    }
+
+    #[test]
+    fn test_named_pools() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.add_message_pool("ui").add_message_pool("gameplay");
+        let pools = app.world().resource::<MessagePools>();
+        let (ui, gameplay) = (pools.get("ui").unwrap(), pools.get("gameplay").unwrap());
+        assert_ne!(ui, gameplay);
+
+        app.world_mut().commands().write_message_after_in("ui", TestMessage("ui"), 1.0);
+        app.world_mut().commands().write_message_after_in("gameplay", TestMessage("gameplay"), 1.0);
+        app.world_mut().entity_mut(gameplay).insert(PausedPool);
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("ui")]);
+        assert!(!app.world().get::<MessagePool>(gameplay).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_marker_pools() {
+        struct UiPool;
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins((WriteAfterPlugin::default(), MessagePoolPlugin::<UiPool>::default()));
+        app.world_mut().spawn((MessagePool::default(), MarkedMessagePool::<UiPool>::default()));
+        app.add_systems(Startup, |mut ui: Single<&mut MarkedMessagePool<UiPool>>, mut pools: Query<&mut MessagePool, Without<GlobalMessagePool>>| {
+            ui.write_after(TestMessage("ui"), 1.0);
+            pools.single_mut().unwrap().write_after(TestMessage("unmarked"), 2.0);
+        });
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("ui")]);
+
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("unmarked")]);
+    }
+
+    #[test]
+    fn test_pool_config() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let filled = app.world_mut().spawn(MessagePool::with_capacity(1)).id();
+        let config = MessagePoolConfig {
+            capacity: Some(2),
+            clock: PoolClock::Real,
+            despawn_when_empty: true,
+            ..default()
+        };
+        let configured = app.world_mut().spawn((MessagePool::default(), config.clone())).id();
+        app.world_mut().flush();
+
+        assert_eq!(app.world().get::<MessagePoolConfig>(filled).unwrap().capacity, Some(1));
+        assert_eq!(app.world().get::<MessagePool>(configured).unwrap().capacity, Some(2));
+        assert_eq!(app.world().get::<PoolClock>(configured), Some(&PoolClock::Real));
+
+        app.world_mut().get_mut::<MessagePoolConfig>(filled).unwrap().capacity = Some(3);
+        app.world_mut().get_mut::<MessagePool>(configured).unwrap().write_after(TestMessage("configured"), Duration::ZERO);
+        app.update();
+        assert_eq!(app.world().get::<MessagePool>(filled).unwrap().capacity, Some(3));
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("configured")]);
+        assert!(app.world().get_entity(configured).is_err());
+    }
+
+    #[test]
+    fn test_despawn_policy() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut pools = Vec::new();
+        for (policy, name) in [(DespawnPolicy::Drop, "dropped"), (DespawnPolicy::WriteAll, "written"), (DespawnPolicy::TransferToGlobal, "transferred")] {
+            let mut pool = MessagePool::default();
+            pool.write_after(TestMessage(name), 2.0);
+            pools.push(app.world_mut().spawn((pool, MessagePoolConfig { on_despawn: policy, ..default() })).id());
+        }
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        for pool in pools {
+            app.world_mut().despawn(pool);
+        }
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::ZERO);
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("written")]);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("transferred")]);
+    }
+
+    #[test]
+    fn test_write_all_order() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut pool = MessagePool::default();
+        pool.sequence()
+            .then(TestMessage("first"), 1.0)
+            .then(TestMessage("third"), 1.0)
+            .schedule();
+        pool.write_after(TestMessage("second"), 1.5);
+        let pool = app.world_mut().spawn((pool, MessagePoolConfig { on_despawn: DespawnPolicy::WriteAll, ..default() })).id();
+        app.update();
+
+        app.world_mut().despawn(pool);
+        app.update();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("first"), TestMessage("second"), TestMessage("third")],
+        );
+    }
+
+    #[test]
+    fn test_state_scoped_pools() {
+        #[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        enum GameState {
+            #[default]
+            Playing,
+            Menu,
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins((bevy::state::app::StatesPlugin, WriteAfterPlugin::default()));
+        app.init_state::<GameState>();
+        app.enable_state_scoped_pools::<GameState>();
+
+        let mut cleared = MessagePool::default();
+        cleared.write_after(TestMessage("cleared"), 5.0);
+        let cleared = app.world_mut().spawn((cleared, ClearPoolOnExit(GameState::Playing))).id();
+        let mut flushed = MessagePool::default();
+        flushed.write_after(TestMessage("flushed"), 5.0);
+        app.world_mut().spawn((flushed, FlushPoolOnExit(GameState::Playing)));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Menu);
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("flushed")]);
+        assert!(app.world().get::<MessagePool>(cleared).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pause_in_state() {
+        #[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        enum GameState {
+            #[default]
+            Playing,
+            Paused,
+        }
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins((bevy::state::app::StatesPlugin, WriteAfterPlugin::default().pause_in_state(GameState::Paused)));
+        app.init_state::<GameState>();
+        app.update();
+
+        app.world_mut().commands().write_message_after(TestMessage("hello"), 1.0);
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Paused);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(2));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("hello")]);
+    }
+
+    #[test]
+    fn test_pool_hierarchy() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+
+        let mut boss = MessagePool::default();
+        boss.write_after(TestMessage("boss"), 1.0);
+        let mut part = MessagePool::default();
+        part.write_after(TestMessage("part"), 1.0);
+        let boss = app.world_mut().spawn((boss, PoolTimeScale(0.5), children![(part, PoolTimeScale(0.5))])).id();
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(2));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("boss")]);
+
+        app.world_mut().entity_mut(boss).insert(PausedPool);
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+
+        app.world_mut().entity_mut(boss).remove::<PausedPool>();
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("part")]);
+    }
+
+    #[test]
+    fn test_tags() {
+        #[derive(Hash)]
+        enum Ability {
+            Fireball,
+            Dash,
+        }
+
+        let mut pool = MessagePool::default();
+        let first = pool.write_after_tagged(TestMessage("burn"), 1.0, Ability::Fireball);
+        pool.write_after_tagged(TestMessage("explode"), 2.0, Ability::Fireball);
+        let dash = pool.write_after(TestMessage("dash"), 1.0);
+        assert!(pool.tag(dash, Ability::Dash));
+        assert!(pool.tag(first, "combo"));
+
+        assert_eq!(pool.count_by_tag(Ability::Fireball), 2);
+        assert_eq!(pool.count_by_tag("combo"), 1);
+        assert_eq!(pool.count_by_tag(String::from("combo")), 0);
+        assert_eq!(pool.cancel_by_tag(Ability::Fireball), 2);
+        assert_eq!(pool.count_by_tag("combo"), 0);
+        assert_eq!(pool.count_by_tag(Ability::Dash), 1);
+    }
+
+    #[test]
+    fn test_pending_count() {
+        let mut pool = MessagePool::default();
+        assert_eq!(pool.len(), 0);
+        assert!(!pool.has_pending::<TestMessage>());
+
+        pool.write_after(TestMessage("a"), 1.0);
+        pool.write_after(TestMessage("b"), 2.0);
+        pool.write_after(OtherMessage, 1.0);
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool.pending_count::<TestMessage>(), 2);
+        assert!(pool.has_pending::<OtherMessage>());
+
+        pool.cancel_all::<OtherMessage>();
+        assert!(!pool.has_pending::<OtherMessage>());
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_iter_pending() {
+        let mut pool = MessagePool::default();
+        let hello = pool.write_after_tagged(TestMessage("hello"), 1.0, "greeting");
+        pool.write_after_keyed("debounce", OtherMessage, 2.0);
+
+        let mut pending: Vec<_> = pool.iter_pending().collect();
+        pending.sort_by_key(|message| message.remaining);
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].handle, hello);
+        assert!(pending[0].type_name.ends_with("TestMessage"));
+        assert_eq!(pending[0].remaining, Duration::from_secs(1));
+        assert!(pending[0].has_tag("greeting"));
+        assert_eq!(pending[1].key, Some("debounce"));
+        assert!(pending[1].type_name.ends_with("OtherMessage"));
+    }
+
+    #[test]
+    fn test_reflect() {
+        use bevy::reflect::GetPath;
+
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default();
+        pool.write_repeating(TestMessage("tick"), 1.0, 3);
+        let entity = app.world_mut().spawn(pool).id();
+
+        let registry = app.world().resource::<AppTypeRegistry>().read();
+        let reflect_component = registry.get_type_data::<ReflectComponent>(TypeId::of::<MessagePool>()).unwrap();
+        let pool = reflect_component.reflect(app.world().entity(entity)).unwrap();
+        assert!(pool.path::<&'static str>("messages.slots[0].value.0.type_name").unwrap().ends_with("TestMessage"));
+        assert_eq!(pool.path::<Timer>("messages.slots[0].value.0.timer").unwrap().remaining(), Duration::from_secs(1));
+        assert_eq!(pool.path::<Option<u32>>("messages.slots[0].value.0.repeats").unwrap(), &Some(3));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut app = App::new();
+        app.add_message::<Autosave>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.register_type::<Autosave>();
+        let mut pool = MessagePool::default();
+        pool.write_after_saved(Autosave(1), 2.0);
+        pool.write_after(TestMessage("unsaved"), 2.0);
+        let saved = app.world_mut().spawn(pool).id();
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        app.update();
+
+        let snapshot = app.world().get::<MessagePool>(saved).unwrap().snapshot();
+        assert_eq!(snapshot.messages.len(), 1);
+        assert_eq!(snapshot.messages[0].remaining, Duration::from_millis(1500));
+        app.world_mut().despawn(saved);
+
+        #[cfg(feature = "serialize")]
+        let snapshot = {
+            use serde::de::DeserializeSeed;
+
+            let registry = app.world().resource::<AppTypeRegistry>().read();
+            let serialized = ron::to_string(&PoolSnapshotSerializer::new(&snapshot, &registry)).unwrap();
+            let mut deserializer = ron::Deserializer::from_str(&serialized).unwrap();
+            PoolSnapshotDeserializer::new(&registry).deserialize(&mut deserializer).unwrap()
+        };
+
+        let mut pool = MessagePool::default();
+        pool.restore(&snapshot, &app.world().resource::<AppTypeRegistry>().read());
+        app.world_mut().spawn(pool);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(1000));
+        app.update();
+        assert!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().next().is_none());
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().collect::<Vec<_>>(), vec![Autosave(1)]);
+    }
+
+    #[cfg(feature = "timeline")]
+    #[test]
+    fn test_timeline() {
+        let mut app = App::new();
+        app.add_message::<Autosave>();
+        app.init_resource::<Time>();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default(), WriteAfterPlugin::default(), TimelinePlugin));
+        app.register_type::<Autosave>();
+        app.update();
+
+        let ron = r#"[
+            (offset: 2.0, message: { "bevy_write_after::tests::Autosave": (2) }),
+            (offset: 0.5, message: { "bevy_write_after::tests::Autosave": (1) }),
+        ]"#;
+        let timeline = TimelineAsset::from_ron(ron, &app.world().resource::<AppTypeRegistry>().read()).unwrap();
+        assert_eq!(timeline.entries[1].offset, Duration::from_millis(500));
+        assert!(TimelineAsset::from_ron("[(offset: 1.0)]", &app.world().resource::<AppTypeRegistry>().read()).is_err());
+
+        let timeline = app.world_mut().resource_mut::<Assets<TimelineAsset>>().add(timeline);
+        let mut query = app.world_mut().query_filtered::<&mut MessagePool, With<GlobalMessagePool>>();
+        query.single_mut(app.world_mut()).unwrap().play(timeline);
+        app.update();
+        assert_eq!(query.single(app.world()).unwrap().len(), 2);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(2));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().collect::<Vec<_>>(), vec![Autosave(1), Autosave(2)]);
+    }
+
+    #[cfg(feature = "timeline")]
+    #[test]
+    fn test_timeline_hot_reload() {
+        let mut app = App::new();
+        app.add_message::<Autosave>();
+        app.init_resource::<Time>();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default(), WriteAfterPlugin::default(), TimelinePlugin));
+        app.register_type::<Autosave>();
+        app.update();
+
+        let timeline = |ron: &str| TimelineAsset::from_ron(ron, &app.world().resource::<AppTypeRegistry>().read()).unwrap();
+        let first = timeline(r#"[
+            (offset: 1.0, message: { "bevy_write_after::tests::Autosave": (1) }),
+            (offset: 3.0, message: { "bevy_write_after::tests::Autosave": (3) }),
+        ]"#);
+        let second = timeline(r#"[
+            (offset: 1.0, message: { "bevy_write_after::tests::Autosave": (10) }),
+            (offset: 2.5, message: { "bevy_write_after::tests::Autosave": (25) }),
+            (offset: 4.0, message: { "bevy_write_after::tests::Autosave": (40) }),
+        ]"#);
+        let handle = app.world_mut().resource_mut::<Assets<TimelineAsset>>().add(first);
+        let mut query = app.world_mut().query_filtered::<&mut MessagePool, With<GlobalMessagePool>>();
+        query.single_mut(app.world_mut()).unwrap().play(handle.clone());
+        app.update();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(2));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().collect::<Vec<_>>(), vec![Autosave(1)]);
+
+        // The change is seen on the next frame, entries before the 2s played so far are skipped.
+        app.world_mut().resource_mut::<Assets<TimelineAsset>>().insert(&handle, second).unwrap();
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::ZERO);
+        app.update();
+        app.update();
+        assert_eq!(query.single(app.world()).unwrap().len(), 2);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().collect::<Vec<_>>(), vec![Autosave(25)]);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(1500));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().collect::<Vec<_>>(), vec![Autosave(40)]);
+        app.update();
+        assert!(query.single(app.world()).unwrap().timelines.is_empty());
+    }
+
+    #[test]
+    fn test_saved_pool_scene() {
+        let mut app = App::new();
+        app.add_message::<Autosave>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default().without_global_pool());
+        app.register_type::<Autosave>();
+        let mut pool = MessagePool::default();
+        pool.write_after_saved(Autosave(1), 1.0);
+        app.world_mut().spawn(pool);
+        app.world_mut().flush();
+
+        let mut scene = DynamicScene::from_world(app.world());
+        SavedPool::save_pools(&mut scene, app.world());
+        app.world_mut().clear_entities();
+
+        #[cfg(feature = "serialize")]
+        let scene = {
+            use bevy::scene::serde::SceneDeserializer;
+            use serde::de::DeserializeSeed;
+
+            let registry = app.world().resource::<AppTypeRegistry>().read();
+            let serialized = scene.serialize(&registry).unwrap();
+            let mut deserializer = ron::Deserializer::from_str(&serialized).unwrap();
+            SceneDeserializer { type_registry: &registry }.deserialize(&mut deserializer).unwrap()
+        };
+
+        scene.write_to_world(app.world_mut(), &mut default()).unwrap();
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        let mut pools = app.world_mut().query::<(&MessagePool, Has<SavedPool>)>();
+        assert_eq!(pools.iter(app.world()).map(|(pool, saved)| (pool.len(), saved)).collect::<Vec<_>>(), vec![(0, false)]);
+        assert_eq!(app.world_mut().resource_mut::<Messages<Autosave>>().drain().collect::<Vec<_>>(), vec![Autosave(1)]);
+    }
+
+    #[test]
+    fn test_tick_pools() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let tick = Duration::from_secs(1) / 60;
+        let mut fixed = MessagePool::with_ticks(tick);
+        fixed.write_after(TestMessage("fixed"), 1.0);
+        let fixed = app.world_mut().spawn(fixed).id();
+        let mut manual = MessagePool::with_manual_ticks(tick);
+        manual.write_after(TestMessage("manual"), 0.5);
+        let manual = app.world_mut().spawn(manual).id();
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(10));
+        app.update();
+
+        for _ in 0..59 {
+            app.world_mut().run_schedule(FixedUpdate);
+        }
+        app.world_mut().commands().entity(manual).advance_ticks(29);
+        app.world_mut().flush();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+        assert_eq!(app.world().get::<MessagePool>(fixed).unwrap().len(), 1);
+
+        app.world_mut().run_schedule(FixedUpdate);
+        app.world_mut().commands().entity(manual).advance_ticks(1);
+        app.world_mut().flush();
+        assert_eq!(
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(),
+            vec![TestMessage("fixed"), TestMessage("manual")]
+        );
+    }
+
+    #[test]
+    fn test_marked_tick_pools() {
+        struct Rollback;
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins((WriteAfterPlugin::default(), MessagePoolPlugin::<Rollback>::default()));
+        let mut pool = MarkedMessagePool::<Rollback>::with_manual_ticks(Duration::from_secs(1) / 60);
+        pool.write_after(TestMessage("manual"), 0.5);
+        let pool = app.world_mut().spawn(pool).id();
+        app.update();
+
+        app.world_mut().commands().entity(pool).advance_marked_ticks::<Rollback>(29);
+        app.world_mut().flush();
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+
+        app.world_mut().commands().entity(pool).advance_marked_ticks::<Rollback>(1);
+        app.world_mut().flush();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("manual")]);
+
+        let mut capped = MarkedMessagePool::<Rollback>::with_capacity(1);
+        capped.write_after(TestMessage("kept"), 1.0);
+        capped.write_after(TestMessage("overflowing"), 1.0);
+        assert_eq!(capped.len(), 1);
+    }
+
+    #[test]
+    fn test_unregistered_messages() {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default().on_unregistered_message(UnregisteredMessagePolicy::Warn));
+        app.world_mut().spawn(MessagePool::default()).get_mut::<MessagePool>().unwrap().write_after(TestMessage("dropped"), 0.0);
+        app.update();
+        assert!(app.world().get_resource::<Messages<TestMessage>>().is_none());
+
+        app.insert_resource(UnregisteredMessagePolicy::Register);
+        app.world_mut().spawn(MessagePool::default()).get_mut::<MessagePool>().unwrap().write_after(TestMessage("registered"), 0.0);
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("registered")]);
+    }
+
+    #[test]
+    fn test_write_after_auto() {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.world_mut().spawn(MessagePool::default()).get_mut::<MessagePool>().unwrap().write_after_auto(TestMessage("auto"), 0.0);
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("auto")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "TestMessage was written, but its type isn't registered")]
+    fn test_unregistered_message_panics() {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.world_mut().spawn(MessagePool::default()).get_mut::<MessagePool>().unwrap().write_after(TestMessage("hello"), 0.0);
+        app.update();
+    }
+
+    #[test]
+    fn test_test_ext() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_plugins(WriteAfterPlugin::default());
+        let mut pool = MessagePool::default();
+        pool.write_after(TestMessage("first"), 1.0);
+        pool.write_after(TestMessage("second"), 3.0);
+        app.world_mut().spawn(pool);
+        app.advance_write_after(Duration::from_millis(500));
+        assert!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().next().is_none());
+        app.advance_write_after(Duration::from_millis(500));
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("first")]);
+        assert!(app.run_until_pool_empty(10));
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("second")]);
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_plugins((bevy::time::TimePlugin, WriteAfterPlugin::default()));
+        app.world_mut().spawn(MessagePool::default()).get_mut::<MessagePool>().unwrap().write_after(TestMessage("timed"), 1.0);
+        app.advance_write_after(Duration::from_secs(1));
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("timed")]);
+    }
+
+    #[test]
+    fn test_headless() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.finish();
+        app.cleanup();
+        app.world_mut().spawn(MessagePool::default()).get_mut::<MessagePool>().unwrap().write_after(TestMessage("headless"), 0.0);
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("headless")]);
+        std::thread::sleep(Duration::from_millis(1));
+        app.update();
+        assert!(app.world().resource::<Time>().elapsed() > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_time_until_next() {
+        let mut pool = MessagePool::default();
+        assert_eq!(pool.time_until_next(), None);
+
+        pool.write_after_frames(TestMessage("frames"), 1);
+        let other = pool.write_after(OtherMessage, 2.0);
+        pool.write_after_handle(other, TestMessage("after"), 0.5);
+        pool.write_after(TestMessage("later"), 3.0);
+        assert_eq!(pool.time_until_next(), Some(Duration::from_secs(2)));
+        assert_eq!(pool.next_of_type::<TestMessage>(), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_write_after_notify() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        let (_, written) = pool.write_after_notify(TestMessage("written"), 1.0);
+        let (handle, cancelled) = pool.write_after_notify(TestMessage("cancelled"), 1.0);
+        pool.cancel(handle);
+        assert_eq!(written.outcome(), None);
+        assert_eq!(bevy::tasks::block_on(cancelled), MessageOutcome::Cancelled);
+
+        let task = std::thread::spawn(move || bevy::tasks::block_on(written));
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(task.join().unwrap(), MessageOutcome::Written);
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("written")]);
+    }
+
+    #[test]
+    fn test_write_after_sender() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let pool = app.world_mut().spawn(MessagePool::default()).id();
+        let sender = app.world().resource::<WriteAfterSender>().clone();
+        std::thread::spawn(move || {
+            sender.write_after(TestMessage("global"), 1.0);
+            sender.write_after_to(pool, TestMessage("entity"), 2.0);
+        })
+        .join()
+        .unwrap();
+
+        app.update();
+        assert_eq!(app.world().get::<MessagePool>(pool).unwrap().len(), 1);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("global")]);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("entity")]);
+    }
+
+    #[test]
+    fn test_global_write_after() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let write_after = app.world().resource::<GlobalWriteAfter>().clone();
+        std::thread::spawn(move || {
+            bevy::tasks::block_on(write_after.write_after_completion(async { TestMessage("path found") }, 1.0));
+        })
+        .join()
+        .unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+
+        app.update();
+        let mut query = app.world_mut().query_filtered::<&MessagePool, With<GlobalMessagePool>>();
+        let remaining = query.single(app.world()).unwrap().time_until_next().unwrap();
+        assert!(remaining <= Duration::from_millis(950), "{remaining:?}");
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect::<Vec<_>>(), vec![TestMessage("path found")]);
+    }
+
+    #[test]
+    fn test_script() {
+        #[derive(Message)]
+        struct Answered;
+
+        #[derive(Resource)]
+        struct Friendly(bool);
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.add_message::<Answered>();
+        app.init_resource::<Time>();
+        app.insert_resource(Friendly(false));
+        app.add_plugins((WriteAfterPlugin::default(), ScriptPlugin));
+        app.update();
+
+        let script = Script::new()
+            .section("knock")
+            .write_after(TestMessage("who's there?"), 0.0)
+            .wait(1.0)
+            .wait_for::<Answered>()
+            .branch(|world: &World| world.resource::<Friendly>().0, "welcome", "knock")
+            .section("welcome")
+            .write_after(TestMessage("come in"), 0.0);
+        let player = app.world_mut().spawn(ScriptPlayer::new(script)).id();
+        fn step(app: &mut App) -> Vec<TestMessage> {
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+            app.update();
+            app.world_mut().resource_mut::<Messages<TestMessage>>().drain().collect()
+        }
+        // Scripts run after the pools, what they schedule is written from the next frame on.
+        assert_eq!(step(&mut app), vec![]);
+        assert_eq!(step(&mut app), vec![TestMessage("who's there?")]);
+        assert_eq!(step(&mut app), vec![]);
+
+        // Not friendly, back to knocking.
+        app.world_mut().write_message(Answered);
+        assert_eq!(step(&mut app), vec![]);
+        app.world_mut().resource_mut::<Friendly>().0 = true;
+        assert_eq!(step(&mut app), vec![TestMessage("who's there?")]);
+
+        app.world_mut().write_message(Answered);
+        assert_eq!(step(&mut app), vec![]);
+        assert_eq!(step(&mut app), vec![TestMessage("come in")]);
+        assert!(app.world().get::<ScriptPlayer>(player).unwrap().is_finished());
+    }
+
+    #[test]
+    fn test_write_after_labeled() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query::<&mut MessagePool>();
+        let mut pool = query.single_mut(app.world_mut()).unwrap();
+        pool.write_after_labeled("boss_phase_2_roar", TestMessage("roar"), 1.0);
+        pool.write_after(TestMessage("roar"), 2.0);
+        let mut labels: Vec<_> = pool.iter_pending().map(|pending| pending.label).collect();
+        labels.sort();
+        assert_eq!(labels, vec![None, Some("boss_phase_2_roar")]);
+        assert_eq!(*pool.path::<Option<&'static str>>("messages.slots[0].value.0.label").unwrap(), Some("boss_phase_2_roar"));
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.update();
+        #[cfg(feature = "debug_messages")]
+        {
+            let scheduled: Vec<_> = app.world_mut().resource_mut::<Messages<MessageScheduled>>().drain().map(|scheduled| scheduled.label).collect();
+            assert_eq!(scheduled, vec![Some("boss_phase_2_roar"), None]);
+            let fired: Vec<_> = app.world_mut().resource_mut::<Messages<MessageFired>>().drain().map(|fired| fired.label).collect();
+            assert_eq!(fired, vec![Some("boss_phase_2_roar")]);
+        }
+    }
+
+    #[cfg(feature = "debug_messages")]
+    #[test]
+    fn test_debug_messages() {
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.add_plugins(WriteAfterPlugin::default());
+        app.update();
+
+        let mut query = app.world_mut().query_filtered::<(Entity, &mut MessagePool), With<GlobalMessagePool>>();
+        let (pool, mut global) = query.single_mut(app.world_mut()).unwrap();
+        let handle = global.write_after(TestMessage("hello"), 1.0);
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(1500));
+        app.update();
+
+        let scheduled: Vec<_> = app.world_mut().resource_mut::<Messages<MessageScheduled>>().drain().collect();
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!((scheduled[0].pool, scheduled[0].handle, scheduled[0].delay), (pool, handle, Duration::from_secs(1)));
+        let fired: Vec<_> = app.world_mut().resource_mut::<Messages<MessageFired>>().drain().collect();
+        assert_eq!(fired.len(), 1);
+        assert_eq!((fired[0].handle, fired[0].overshoot), (handle, Duration::from_millis(500)));
+        assert!(fired[0].type_name.ends_with("TestMessage"));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        use bevy::diagnostic::{DiagnosticPath, DiagnosticsPlugin, DiagnosticsStore};
+
+        let mut app = App::new();
+        app.add_message::<TestMessage>();
+        app.init_resource::<Time>();
+        app.init_resource::<Time<Real>>();
+        app.add_plugins((DiagnosticsPlugin, WriteAfterPlugin::default(), WriteAfterDiagnosticsPlugin));
+        let mut pool = MessagePool::default();
+        pool.write_after(TestMessage("a"), 1.0);
+        pool.write_after(TestMessage("b"), 1.0);
+        pool.write_after(TestMessage("c"), 2.0);
+        app.world_mut().spawn(pool);
+        app.update();
+
+        let value = |app: &App, path: &DiagnosticPath| {
+            app.world().resource::<DiagnosticsStore>().get(path).and_then(|diagnostic| diagnostic.value())
+        };
+        assert_eq!(value(&app, &WriteAfterDiagnosticsPlugin::PENDING_MESSAGES), Some(3.0));
+        assert_eq!(value(&app, &WriteAfterDiagnosticsPlugin::ACTIVE_POOLS), Some(1.0));
+        assert_eq!(value(&app, &WriteAfterDiagnosticsPlugin::MESSAGES_PER_SECOND), None);
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        app.world_mut().resource_mut::<Time<Real>>().advance_by(Duration::from_millis(500));
+        app.update();
+        assert_eq!(value(&app, &WriteAfterDiagnosticsPlugin::PENDING_MESSAGES), Some(1.0));
+        assert_eq!(value(&app, &WriteAfterDiagnosticsPlugin::MESSAGES_PER_SECOND), Some(4.0));
+    }
+
+    #[cfg(feature = "inspector")]
+    #[test]
+    fn test_inspector() {
+        use bevy_egui::{EguiContext, EguiPrimaryContextPass, PrimaryEguiContext, egui};
+
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins((WriteAfterPlugin::default(), WriteAfterInspectorPlugin));
+        let context = app.world_mut().spawn(PrimaryEguiContext).id();
+        app.update();
+
+        // Runs the pass by hand, as `EguiPlugin` needs a window.
+        app.world_mut().get_mut::<EguiContext>(context).unwrap().get_mut().begin_pass(egui::RawInput::default());
+        app.world_mut().run_schedule(EguiPrimaryContextPass);
+        let mut context = app.world_mut().get_mut::<EguiContext>(context).unwrap();
+        let _ = context.get_mut().end_pass();
+        assert!(context.get_mut().memory(|memory| memory.area_rect(WriteAfterInspectorPlugin::TITLE).is_some()));
+    }
+
+    #[cfg(feature = "replicon")]
+    #[test]
+    fn test_replicated_schedule() {
+        use bevy::state::app::StatesPlugin;
+        use bevy_replicon::prelude::*;
+        use bevy_replicon::test_app::ServerTestAppExt;
+
+        let mut server = App::new();
+        let mut client = App::new();
+        for app in [&mut server, &mut client] {
+            app.add_message::<TestMessage>();
+            app.init_resource::<Time>();
+            app.add_plugins((StatesPlugin, RepliconPlugins.set(ServerPlugin::new(PostUpdate))));
+            app.add_plugins((WriteAfterPlugin::default(), WriteAfterReplicationPlugin));
+            app.finish();
+        }
+        server.connect_client(&mut client);
+
+        let mut pool = MessagePool::default();
+        let handle = pool.write_after(TestMessage("boom"), 2.0);
+        let pool = server.world_mut().spawn((pool, Replicated, ReplicatedSchedule::default())).id();
+        server.update();
+        server.exchange_with_client(&mut client);
+        client.update();
+
+        let mut schedules = client.world_mut().query::<&ReplicatedSchedule>();
+        let schedule = schedules.single(client.world()).unwrap();
+        assert_eq!(schedule.messages.len(), 1);
+        assert_eq!(schedule.messages[0].handle, handle);
+        assert!(schedule.messages[0].type_path.ends_with("TestMessage"));
+        assert_eq!(schedule.time_until_next(), Some(Duration::from_secs(2)));
+
+        client.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        client.update();
+        assert_eq!(schedules.single(client.world()).unwrap().time_until_next(), Some(Duration::from_millis(1500)));
+
+        server.world_mut().get_mut::<MessagePool>(pool).unwrap().cancel(handle);
+        server.update();
+        server.exchange_with_client(&mut client);
+        client.update();
+        assert!(schedules.single(client.world()).unwrap().messages.is_empty());
+
+        // Real time pools are counted down with `Time<Real>`, on both sides.
+        let mut ui = MessagePool::default();
+        ui.write_after(TestMessage("toast"), 2.0);
+        let ui = server.world_mut().spawn((ui, PoolClock::Real, Replicated, ReplicatedSchedule::default())).id();
+        server.update();
+        server.exchange_with_client(&mut client);
+        client.update();
+        let mut schedules = client.world_mut().query::<(&ReplicatedSchedule, &PoolClock)>();
+        let mut remaining = |client: &App| {
+            let (schedule, clock) = schedules.iter(client.world()).find(|(schedule, _)| !schedule.messages.is_empty()).unwrap();
+            assert_eq!(*clock, PoolClock::Real);
+            schedule.time_until_next()
+        };
+        assert_eq!(remaining(&client), Some(Duration::from_secs(2)));
+
+        client.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(500));
+        client.update();
+        assert_eq!(remaining(&client), Some(Duration::from_secs(2)));
+
+        let mut real = Time::<Real>::default();
+        real.update_with_duration(Duration::ZERO);
+        real.update_with_duration(Duration::from_millis(500));
+        client.insert_resource(real);
+        client.update();
+        assert_eq!(remaining(&client), Some(Duration::from_millis(1500)));
+        assert_eq!(server.world().get::<ReplicatedSchedule>(ui).unwrap().time_until_next(), Some(Duration::from_secs(2)));
+    }
+}
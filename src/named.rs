@@ -0,0 +1,76 @@
+//! Pools registered under a label at app build time, and the [`App`] extension registering them.
+
+use std::borrow::Cow;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::state::state::StateTransition;
+use bevy::state::state::StateTransitionSystems;
+
+use crate::MessagePool;
+use crate::state::empty_pools_on_exit_state;
+
+/// Entities of the pools registered with [`WriteAfterAppExt::add_message_pool`], by label.
+///
+/// Each labeled pool is a regular entity, so subsystems can be paused with
+/// [`PausedPool`](crate::PausedPool) or cleared independently.
+#[derive(Resource, Default, Debug)]
+pub struct MessagePools {
+    pools: HashMap<Cow<'static, str>, Entity>,
+}
+
+impl MessagePools {
+    /// Entity of the pool registered under `label`.
+    pub fn get(&self, label: &str) -> Option<Entity> {
+        self.pools.get(label).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Entity)> {
+        self.pools.iter().map(|(label, &entity)| (label.as_ref(), entity))
+    }
+}
+
+/// Extension trait for [`App`] to register labeled and state scoped pools.
+pub trait WriteAfterAppExt {
+    /// Spawns a [`MessagePool`] registered under `label` in [`MessagePools`].
+    ///
+    /// Registering the same label again keeps the existing pool.
+    fn add_message_pool(&mut self, label: impl Into<Cow<'static, str>>) -> &mut Self;
+
+    /// Empties pools marked with [`ClearPoolOnExit<S>`](crate::ClearPoolOnExit) or
+    /// [`FlushPoolOnExit<S>`](crate::FlushPoolOnExit) when the app exits their state.
+    fn enable_state_scoped_pools<S: States>(&mut self) -> &mut Self;
+}
+
+impl WriteAfterAppExt for App {
+    fn add_message_pool(&mut self, label: impl Into<Cow<'static, str>>) -> &mut Self {
+        let label = label.into();
+        let world = self.world_mut();
+        if world.get_resource::<MessagePools>().is_some_and(|pools| pools.pools.contains_key(&label)) {
+            return self;
+        }
+        let entity = world.spawn((MessagePool::default(), Name::new(label.clone()))).id();
+        world.get_resource_or_init::<MessagePools>().pools.insert(label, entity);
+        self
+    }
+
+    fn enable_state_scoped_pools<S: States>(&mut self) -> &mut Self {
+        self.add_systems(
+            StateTransition,
+            empty_pools_on_exit_state::<S>.in_set(StateTransitionSystems::ExitSchedules),
+        )
+    }
+}
+
+/// Runs `f` on the pool registered under `label`.
+///
+/// # Panics
+///
+/// If no pool is registered under `label`, or it was despawned.
+pub(crate) fn with_named_pool<T>(world: &mut World, label: &str, f: impl FnOnce(&mut MessagePool) -> T) -> T {
+    let entity = world.get_resource::<MessagePools>().and_then(|pools| pools.get(label));
+    let mut pool = entity
+        .and_then(|entity| world.get_mut::<MessagePool>(entity))
+        .unwrap_or_else(|| panic!("no message pool labeled {label:?}"));
+    f(&mut pool)
+}
@@ -0,0 +1,92 @@
+//! System parameters scheduling from the [`GlobalMessagePool`].
+
+use std::ops::{Deref, DerefMut};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::prelude::ShortName;
+
+use crate::{GlobalMessagePool, IntoDelay, MessagePool, TypedMessageHandle, TypedMessagePool};
+
+/// The [`GlobalMessagePool`] as a system parameter.
+///
+/// Dereferences to [`MessagePool`], so every scheduling method is available directly.
+/// Systems using it are skipped while the global pool doesn't exist.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_write_after::WriteAfter;
+/// # #[derive(Message)]
+/// # struct MyMessage;
+/// fn some_system(mut write_after: WriteAfter) {
+///     write_after.write_after(MyMessage, 1.0);
+/// }
+/// # bevy::ecs::system::assert_is_system(some_system);
+/// ```
+#[derive(SystemParam)]
+pub struct WriteAfter<'w, 's> {
+    pool: Single<'w, 's, &'static mut MessagePool, With<GlobalMessagePool>>,
+}
+
+impl Deref for WriteAfter<'_, '_> {
+    type Target = MessagePool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pool
+    }
+}
+
+impl DerefMut for WriteAfter<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.pool
+    }
+}
+
+/// Writes messages of type `M` from the [`GlobalMessagePool`] after a delay, the delayed counterpart of [`MessageWriter`].
+///
+/// Messages are kept by value in the [`TypedMessagePool<M>`] of the global pool, see
+/// [`TypedMessagePoolPlugin<M>`](crate::TypedMessagePoolPlugin). Systems using it are skipped
+/// while the global pool doesn't exist.
+///
+/// # Panics
+///
+/// Scheduling panics if `TypedMessagePoolPlugin::<M>` wasn't added.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_write_after::DelayedMessageWriter;
+/// # #[derive(Message)]
+/// # struct MyMessage;
+/// fn some_system(mut writer: MessageWriter<MyMessage>, mut delayed: DelayedMessageWriter<MyMessage>) {
+///     writer.write(MyMessage);
+///     delayed.write_after(MyMessage, 1.0);
+/// }
+/// # bevy::ecs::system::assert_is_system(some_system);
+/// ```
+#[derive(SystemParam)]
+pub struct DelayedMessageWriter<'w, 's, M: Message> {
+    // Optional so systems still run without the plugin, and its absence is reported by name.
+    pool: Single<'w, 's, Option<&'static mut TypedMessagePool<M>>, With<GlobalMessagePool>>,
+}
+
+impl<M: Message> DelayedMessageWriter<'_, '_, M> {
+    /// Writes `message` after `delay`, see [`TypedMessagePool::write_after`].
+    pub fn write_after(&mut self, message: M, delay: impl IntoDelay) -> TypedMessageHandle<M> {
+        self.pool().write_after(message, delay)
+    }
+
+    /// Cancels a message scheduled through this writer, see [`TypedMessagePool::cancel`].
+    pub fn cancel(&mut self, handle: TypedMessageHandle<M>) -> bool {
+        self.pool().cancel(handle)
+    }
+
+    fn pool(&mut self) -> &mut TypedMessagePool<M> {
+        match self.pool.as_deref_mut() {
+            Some(pool) => pool,
+            None => panic!(
+                "DelayedMessageWriter<{0}> requires TypedMessagePoolPlugin::<{0}>, add it to the app",
+                ShortName::of::<M>(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Schedules of server pools replicated to `bevy_replicon` clients, for countdowns of server-authoritative messages.
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{MessageHandle, MessagePool, PoolClock, PoolScale, ProcessingSchedule, WriteAfterSet};
+
+/// Replicates the [`ReplicatedSchedule`] of the unmarked [`MessagePool`]s of the server to
+/// clients, with the `replicon` feature.
+///
+/// Clients count the remaining times down on their own, so a schedule is only sent again when
+/// its messages change or their remaining times drift from the countdown by more than
+/// [`WriteAfterReplicationPlugin::TOLERANCE`], like when a message is rescheduled. The
+/// [`PoolClock`] of the pools is replicated too, so clients count down on the same clock.
+///
+/// Requires `RepliconPlugins` and [`WriteAfterPlugin`](crate::WriteAfterPlugin), added before it.
+#[derive(Default)]
+pub struct WriteAfterReplicationPlugin;
+
+impl WriteAfterReplicationPlugin {
+    /// Drift from the countdown of clients above which a schedule is sent again.
+    pub const TOLERANCE: Duration = Duration::from_millis(100);
+}
+
+impl Plugin for WriteAfterReplicationPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = ProcessingSchedule::of(app);
+        app
+            .replicate::<ReplicatedSchedule>()
+            .replicate::<PoolClock>()
+            .add_systems(schedule, (
+                count_down_schedules.before(WriteAfterSet),
+                sync_schedules.after(WriteAfterSet).run_if(in_state(ServerState::Running)),
+            ))
+            .add_systems(FixedUpdate, count_down_fixed_schedules.before(WriteAfterSet));
+    }
+}
+
+/// Pending messages of the [`MessagePool`] of its entity, soonest first.
+///
+/// Insert it on the server with `Replicated` on the pools to replicate. Clients only receive
+/// the type path and remaining time of each message, not the message itself.
+///
+/// Requires [`WriteAfterReplicationPlugin`].
+#[derive(Component, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReplicatedSchedule {
+    pub messages: Vec<ReplicatedMessage>,
+}
+
+impl ReplicatedSchedule {
+    /// Time until the soonest message that isn't paused is written.
+    pub fn time_until_next(&self) -> Option<Duration> {
+        self.messages.iter().filter(|message| !message.paused).map(|message| message.remaining).min()
+    }
+}
+
+/// A message of a [`ReplicatedSchedule`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplicatedMessage {
+    /// Handle of the message in the server pool.
+    pub handle: MessageHandle,
+    /// Same as [`PendingMessage::type_name`](crate::PendingMessage::type_name).
+    pub type_path: Cow<'static, str>,
+    pub label: Option<Cow<'static, str>>,
+    /// Time left until the message is written, counted down between updates.
+    pub remaining: Duration,
+    pub paused: bool,
+}
+
+impl ReplicatedMessage {
+    /// Whether `self` is `other` counted down, only off by less than the tolerance.
+    fn matches(&self, other: &Self) -> bool {
+        self.handle == other.handle
+            && self.paused == other.paused
+            && self.remaining.abs_diff(other.remaining) <= WriteAfterReplicationPlugin::TOLERANCE
+    }
+}
+
+/// Counts the remaining times down on the server too, to tell what clients predict, with the
+/// clock and scale the pool is ticked with.
+fn count_down_schedules(
+    time: Res<Time>,
+    real_time: Option<Res<Time<Real>>>,
+    scale: PoolScale,
+    schedules: Query<(Entity, &mut ReplicatedSchedule, Option<&PoolClock>)>,
+) {
+    for (entity, schedule, clock) in schedules {
+        let delta = match clock.copied().unwrap_or_default() {
+            PoolClock::Virtual => time.delta(),
+            PoolClock::Real => real_time.as_ref().map(|time| time.delta()).unwrap_or_default(),
+            PoolClock::Fixed => continue,
+        };
+        if let Some(delta) = scale.scale(entity, delta) {
+            count_down(schedule, delta);
+        }
+    }
+}
+
+fn count_down_fixed_schedules(
+    time: Res<Time>,
+    scale: PoolScale,
+    schedules: Query<(Entity, &mut ReplicatedSchedule, &PoolClock)>,
+) {
+    for (entity, schedule, clock) in schedules {
+        if *clock == PoolClock::Fixed && let Some(delta) = scale.scale(entity, time.delta()) {
+            count_down(schedule, delta);
+        }
+    }
+}
+
+fn count_down(mut schedule: Mut<ReplicatedSchedule>, delta: Duration) {
+    for message in schedule.bypass_change_detection().messages.iter_mut().filter(|message| !message.paused) {
+        message.remaining = message.remaining.saturating_sub(delta);
+    }
+}
+
+fn sync_schedules(mut pools: Query<(&MessagePool, &mut ReplicatedSchedule)>) {
+    for (pool, mut schedule) in &mut pools {
+        let mut messages: Vec<_> = pool
+            .iter_pending()
+            .map(|message| ReplicatedMessage {
+                handle: message.handle,
+                type_path: Cow::Borrowed(message.type_name),
+                label: message.label.map(Cow::Borrowed),
+                remaining: message.remaining,
+                paused: message.paused,
+            })
+            .collect();
+        messages.sort_by_key(|message| (message.remaining, message.handle));
+        let predicted = schedule.messages.len() == messages.len()
+            && schedule.messages.iter().zip(&messages).all(|(predicted, message)| predicted.matches(message));
+        if !predicted {
+            schedule.messages = messages;
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Scripted sequences of messages with sections, jumps, waits and branches, played through pools.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::{IntoDelay, MessageHandle, MessagePool, ProcessingSchedule, WriteAfterSet};
+
+/// Runs every [`ScriptPlayer`], right after the pools were processed in
+/// [`WriteAfterPlugin::schedule`](crate::WriteAfterPlugin::schedule).
+///
+/// Requires [`WriteAfterPlugin`](crate::WriteAfterPlugin), added before it.
+#[derive(Default)]
+pub struct ScriptPlugin;
+
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(ProcessingSchedule::of(app), run_scripts.after(WriteAfterSet));
+    }
+}
+
+type ScheduleFn = Box<dyn Fn(&mut MessagePool) + Send + Sync + 'static>;
+
+type Predicate = Box<dyn Fn(&World) -> bool + Send + Sync + 'static>;
+
+/// Checks whether the awaited message was written since the wait started.
+type MessageWait = Box<dyn FnMut(&World) -> bool + Send + Sync + 'static>;
+
+enum ScriptStep {
+    Schedule(ScheduleFn),
+    Wait(Duration),
+    WaitFor(fn(&World) -> MessageWait),
+    Jump(Cow<'static, str>),
+    Branch {
+        predicate: Predicate,
+        then: Cow<'static, str>,
+        otherwise: Cow<'static, str>,
+    },
+}
+
+/// A scripted sequence of steps, played into the [`MessagePool`] of an entity by a [`ScriptPlayer`].
+///
+/// Steps run one after the other until one waits, for a delay or for a message. Sections label
+/// the step following them, to be jumped to or branched to.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_write_after::{Script, ScriptPlayer};
+/// # #[derive(Message, Clone)]
+/// # struct Say(&'static str);
+/// # #[derive(Message)]
+/// # struct Answered;
+/// # #[derive(Resource)]
+/// # struct Friendly(bool);
+/// let script = Script::new()
+///     .write_after(Say("Who's there?"), 0.5)
+///     .wait_for::<Answered>()
+///     .branch(|world: &World| world.resource::<Friendly>().0, "welcome", "alarm")
+///     .section("welcome")
+///     .write_after(Say("Come in."), 1.0)
+///     .jump("end")
+///     .section("alarm")
+///     .write_after(Say("Guards!"), 0.0)
+///     .section("end");
+/// # let _ = ScriptPlayer::new(script);
+/// ```
+#[derive(Default)]
+pub struct Script {
+    steps: Vec<ScriptStep>,
+    sections: HashMap<Cow<'static, str>, usize>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes `message` after `delay` from the pool, without waiting for it.
+    pub fn write_after<M: Message + Clone>(self, message: M, delay: impl IntoDelay) -> Self {
+        let delay = delay.into_delay();
+        self.schedule(move |pool: &mut MessagePool| {
+            pool.write_after(message.clone(), delay);
+        })
+    }
+
+    /// Runs `f` on the pool, to schedule anything else like a sequence or a timeline.
+    pub fn schedule<F: Fn(&mut MessagePool) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.steps.push(ScriptStep::Schedule(Box::new(f)));
+        self
+    }
+
+    /// Waits `delay` on the pool, so pausing or scaling the pool applies to the script too.
+    pub fn wait(mut self, delay: impl IntoDelay) -> Self {
+        self.steps.push(ScriptStep::Wait(delay.into_delay()));
+        self
+    }
+
+    /// Waits until a message of type `M` is written, by the pool or anything else.
+    pub fn wait_for<M: Message>(mut self) -> Self {
+        self.steps.push(ScriptStep::WaitFor(|world| {
+            let mut cursor = world.get_resource::<Messages<M>>().map(Messages::get_cursor_current).unwrap_or_default();
+            Box::new(move |world: &World| {
+                world.get_resource::<Messages<M>>().is_some_and(|messages| cursor.read(messages).next().is_some())
+            })
+        }));
+        self
+    }
+
+    /// Labels the next step, for [`Script::jump`] and [`Script::branch`].
+    pub fn section(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.sections.insert(label.into(), self.steps.len());
+        self
+    }
+
+    /// Continues at the section `label`.
+    pub fn jump(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.steps.push(ScriptStep::Jump(label.into()));
+        self
+    }
+
+    /// Continues at the section `then` if `predicate` holds for the world, at `otherwise` if not.
+    pub fn branch<P>(mut self, predicate: P, then: impl Into<Cow<'static, str>>, otherwise: impl Into<Cow<'static, str>>) -> Self
+    where
+        P: Fn(&World) -> bool + Send + Sync + 'static,
+    {
+        self.steps.push(ScriptStep::Branch {
+            predicate: Box::new(predicate),
+            then: then.into(),
+            otherwise: otherwise.into(),
+        });
+        self
+    }
+}
+
+enum Waiting {
+    Delay(MessageHandle),
+    Message(MessageWait),
+}
+
+/// Plays a [`Script`] into the [`MessagePool`] of its entity, inserting one if needed.
+///
+/// Requires [`ScriptPlugin`].
+#[derive(Component, Default)]
+#[require(MessagePool)]
+pub struct ScriptPlayer {
+    script: Arc<Script>,
+    position: usize,
+    waiting: Option<Waiting>,
+    finished: bool,
+}
+
+impl ScriptPlayer {
+    /// Steps run by a single run of [`ScriptPlugin`] before the script is continued on the next one,
+    /// so jumps looping without waiting don't freeze the app.
+    const MAX_STEPS_PER_RUN: usize = 1024;
+
+    pub fn new(script: impl Into<Arc<Script>>) -> Self {
+        Self {
+            script: script.into(),
+            ..default()
+        }
+    }
+
+    /// Whether the script ran past its last step or jumped to a missing section.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Continues at the section `label`, dropping what the script was waiting for.
+    ///
+    /// Returns `false` if the script has no such section.
+    pub fn jump(&mut self, label: &str) -> bool {
+        let Some(&position) = self.script.sections.get(label) else {
+            return false;
+        };
+        self.position = position;
+        self.waiting = None;
+        self.finished = false;
+        true
+    }
+
+    fn run(&mut self, world: &mut World, entity: Entity) {
+        for _ in 0..Self::MAX_STEPS_PER_RUN {
+            let waited = match &mut self.waiting {
+                Some(Waiting::Delay(handle)) => {
+                    world.get::<MessagePool>(entity).is_some_and(|pool| pool.get(*handle).is_none())
+                }
+                Some(Waiting::Message(written)) => written(world),
+                None => true,
+            };
+            if !waited {
+                return;
+            }
+            self.waiting = None;
+
+            let script = self.script.clone();
+            let Some(step) = script.steps.get(self.position) else {
+                self.finished = true;
+                return;
+            };
+            self.position += 1;
+            let label = match step {
+                ScriptStep::Schedule(schedule) => {
+                    if let Some(mut pool) = world.get_mut::<MessagePool>(entity) {
+                        schedule(&mut pool);
+                    }
+                    continue;
+                }
+                ScriptStep::Wait(delay) => {
+                    let Some(mut pool) = world.get_mut::<MessagePool>(entity) else {
+                        continue;
+                    };
+                    self.waiting = Some(Waiting::Delay(pool.command_after_fn(|_: &mut World| {}, *delay)));
+                    continue;
+                }
+                ScriptStep::WaitFor(start) => {
+                    self.waiting = Some(Waiting::Message(start(world)));
+                    continue;
+                }
+                ScriptStep::Jump(label) => label,
+                ScriptStep::Branch { predicate, then, otherwise } => {
+                    if predicate(world) { then } else { otherwise }
+                }
+            };
+            if !self.jump(label) {
+                warn!("script of {entity} jumped to the missing section {label:?}");
+                self.finished = true;
+                return;
+            }
+        }
+    }
+}
+
+fn run_scripts(world: &mut World) {
+    let mut players = world.query::<(Entity, &ScriptPlayer)>();
+    let playing: Vec<_> = players.iter(world).filter(|(_, player)| !player.finished).map(|(entity, _)| entity).collect();
+    for entity in playing {
+        let Some(mut player) = world.get_mut::<ScriptPlayer>(entity) else {
+            continue;
+        };
+        // Taken out for the steps to access the world.
+        let mut running = std::mem::take(&mut *player);
+        running.run(world, entity);
+        if let Some(mut player) = world.get_mut::<ScriptPlayer>(entity) {
+            *player = running;
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Slot storage of the messages of a pool.
+
+use std::ops::{Index, IndexMut};
+
+use bevy::reflect::Reflect;
+use smallvec::SmallVec;
+
+use crate::MessageHandle;
+
+/// Slots stored inline before spilling to the heap. Most per-entity pools never hold more.
+const INLINE: usize = 2;
+
+/// Reuses the slots of removed messages. A handle packs the slot index with the generation of the
+/// slot, so lookups and removals are `O(1)` and handles of removed messages never match a later one.
+#[derive(Reflect)]
+pub(crate) struct Slab<T> {
+    slots: SmallVec<[Slot<T>; INLINE]>,
+    /// Indices of vacant slots. May contain occupied or duplicate indices, which are skipped.
+    free: SmallVec<[u32; INLINE]>,
+    len: usize,
+}
+
+#[derive(Reflect)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Self {
+            slots: SmallVec::new(),
+            free: SmallVec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Slab<T> {
+    fn handle(index: u32, generation: u32) -> MessageHandle {
+        MessageHandle((generation as u64) << 32 | index as u64)
+    }
+
+    fn split(handle: MessageHandle) -> (usize, u32) {
+        (handle.0 as u32 as usize, (handle.0 >> 32) as u32)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.slots.reserve(additional.saturating_sub(self.free.len()));
+    }
+
+    /// Stores the value built from its handle.
+    pub(crate) fn insert_with(&mut self, f: impl FnOnce(MessageHandle) -> T) -> MessageHandle {
+        while let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            if slot.value.is_none() {
+                slot.generation = slot.generation.wrapping_add(1);
+                let handle = Self::handle(index, slot.generation);
+                slot.value = Some(f(handle));
+                self.len += 1;
+                return handle;
+            }
+        }
+
+        let index = u32::try_from(self.slots.len()).expect("too many pending messages");
+        let handle = Self::handle(index, 0);
+        self.slots.push(Slot { generation: 0, value: Some(f(handle)) });
+        self.len += 1;
+        handle
+    }
+
+    pub(crate) fn get(&self, handle: MessageHandle) -> Option<&T> {
+        let (index, generation) = Self::split(handle);
+        self.slots.get(index).filter(|slot| slot.generation == generation)?.value.as_ref()
+    }
+
+    pub(crate) fn get_mut(&mut self, handle: MessageHandle) -> Option<&mut T> {
+        let (index, generation) = Self::split(handle);
+        self.slots.get_mut(index).filter(|slot| slot.generation == generation)?.value.as_mut()
+    }
+
+    pub(crate) fn contains(&self, handle: MessageHandle) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub(crate) fn remove(&mut self, handle: MessageHandle) -> Option<T> {
+        let (index, generation) = Self::split(handle);
+        let value = self.slots.get_mut(index).filter(|slot| slot.generation == generation)?.value.take()?;
+        self.free.push(index as u32);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (MessageHandle, &mut T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            Some((Self::handle(index as u32, slot.generation), slot.value.as_mut()?))
+        })
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.value.as_ref().is_some_and(|value| !f(value)) {
+                slot.value = None;
+                self.free.push(index as u32);
+                self.len -= 1;
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+}
+
+impl<T> Index<MessageHandle> for Slab<T> {
+    type Output = T;
+
+    fn index(&self, handle: MessageHandle) -> &T {
+        self.get(handle).expect("no message for handle")
+    }
+}
+
+impl<T> IndexMut<MessageHandle> for Slab<T> {
+    fn index_mut(&mut self, handle: MessageHandle) -> &mut T {
+        self.get_mut(handle).expect("no message for handle")
+    }
+}
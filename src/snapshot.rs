@@ -0,0 +1,283 @@
+//! Snapshots of the pending messages of a pool, to save them and schedule them again later.
+
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bevy::ecs::lifecycle::HookContext;
+use bevy::ecs::world::DeferredWorld;
+use bevy::prelude::*;
+use bevy::reflect::{FromType, TypeRegistry};
+#[cfg(feature = "serialize")]
+use bevy::reflect::serde::{ReflectDeserializeWithRegistry, ReflectSerializeWithRegistry};
+
+use crate::{MessagePool, QueuedMessage};
+
+/// Messages saved by [`MessagePool::snapshot`](crate::MessagePool::snapshot), in scheduling order.
+///
+/// With the `serialize` feature it is written with [`PoolSnapshotSerializer`] and read back with
+/// [`PoolSnapshotDeserializer`], like a `DynamicScene`. Reflected snapshots serialize the same way.
+#[derive(Debug, Default, Clone, Reflect)]
+#[reflect(opaque, Debug, Default, Clone)]
+#[cfg_attr(feature = "serialize", reflect(SerializeWithRegistry, DeserializeWithRegistry))]
+pub struct PoolSnapshot {
+    pub messages: Vec<SavedMessage>,
+}
+
+/// A message of a [`PoolSnapshot`].
+#[derive(Debug)]
+pub struct SavedMessage {
+    pub message: Box<dyn PartialReflect>,
+    /// Time the message had left when the snapshot was taken.
+    pub remaining: Duration,
+    pub priority: i32,
+    pub key: Option<Cow<'static, str>>,
+}
+
+impl Clone for SavedMessage {
+    fn clone(&self) -> Self {
+        Self {
+            message: self.message.to_dynamic(),
+            remaining: self.remaining,
+            priority: self.priority,
+            key: self.key.clone(),
+        }
+    }
+}
+
+/// Restores its snapshot into the [`MessagePool`] of its entity when inserted, then removes itself.
+///
+/// This is how pools go into a `DynamicScene`: the messages of the snapshot start ticking once
+/// the scene is spawned. [`SavedPool::save_pools`] puts them into an extracted scene.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_write_after::SavedPool;
+/// fn save_scene(world: &World) -> DynamicScene {
+///     let mut scene = DynamicScene::from_world(world);
+///     SavedPool::save_pools(&mut scene, world);
+///     scene
+/// }
+/// ```
+#[derive(Component, Debug, Default, Clone, Reflect)]
+#[reflect(Component, Debug, Default, Clone)]
+#[component(on_insert = restore_saved_pool)]
+pub struct SavedPool(pub PoolSnapshot);
+
+impl SavedPool {
+    /// Replaces the [`MessagePool`] of every entity of `scene` with a `SavedPool` holding its
+    /// snapshot, since the reflected messages of a pool can't be written.
+    pub fn save_pools(scene: &mut DynamicScene, world: &World) {
+        for entity in &mut scene.entities {
+            let Some(pool) = world.get::<MessagePool>(entity.entity) else {
+                continue;
+            };
+            entity.components.retain(|component| {
+                component.get_represented_type_info().is_none_or(|info| info.type_id() != TypeId::of::<MessagePool>())
+            });
+            entity.components.push(Box::new(SavedPool(pool.snapshot())));
+        }
+    }
+}
+
+fn restore_saved_pool(mut world: DeferredWorld, context: HookContext) {
+    world.commands().queue(move |world: &mut World| {
+        let Ok(mut entity) = world.get_entity_mut(context.entity) else {
+            return;
+        };
+        let Some(SavedPool(snapshot)) = entity.take::<SavedPool>() else {
+            return;
+        };
+        let registry = entity.world().resource::<AppTypeRegistry>().clone();
+        entity.entry::<MessagePool>().or_default().get_mut().restore(&snapshot, &registry.read());
+    });
+}
+
+/// Type data letting [`MessagePool::restore`](crate::MessagePool::restore) schedule reflected
+/// messages of a type, registered with `#[reflect(DelayedMessage)]`.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_write_after::ReflectDelayedMessage;
+/// #[derive(Message, Reflect)]
+/// #[reflect(DelayedMessage)]
+/// struct Autosave;
+/// ```
+#[derive(Clone)]
+pub struct ReflectDelayedMessage {
+    from_reflect: fn(&dyn PartialReflect, Duration) -> Option<QueuedMessage>,
+}
+
+impl ReflectDelayedMessage {
+    pub(crate) fn schedule(message: &dyn PartialReflect, delay: Duration, registry: &TypeRegistry) -> Option<QueuedMessage> {
+        let type_id = message.get_represented_type_info()?.type_id();
+        (registry.get_type_data::<Self>(type_id)?.from_reflect)(message, delay)
+    }
+}
+
+impl<M: Message + FromReflect> FromType<M> for ReflectDelayedMessage {
+    fn from_type() -> Self {
+        Self {
+            from_reflect: |message, delay| Some(QueuedMessage::saved(M::from_reflect(message)?, delay)),
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+pub use self::serialize::{PoolSnapshotDeserializer, PoolSnapshotSerializer};
+
+#[cfg(feature = "serialize")]
+mod serialize {
+    use std::fmt;
+
+    use bevy::reflect::TypeRegistry;
+    use bevy::reflect::serde::{DeserializeWithRegistry, ReflectDeserializer, ReflectSerializer, SerializeWithRegistry};
+    use serde::de::{DeserializeSeed, Error, IgnoredAny, MapAccess, SeqAccess, Visitor};
+    use serde::ser::{SerializeSeq, SerializeStruct};
+    use serde::{Deserializer, Serialize, Serializer};
+
+    use super::{PoolSnapshot, SavedMessage};
+
+    const FIELDS: &[&str] = &["message", "remaining", "priority", "key"];
+
+    /// Serializes a [`PoolSnapshot`], writing each message with its type path.
+    pub struct PoolSnapshotSerializer<'a> {
+        snapshot: &'a PoolSnapshot,
+        registry: &'a TypeRegistry,
+    }
+
+    impl<'a> PoolSnapshotSerializer<'a> {
+        pub fn new(snapshot: &'a PoolSnapshot, registry: &'a TypeRegistry) -> Self {
+            Self { snapshot, registry }
+        }
+    }
+
+    impl Serialize for PoolSnapshotSerializer<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(self.snapshot.messages.len()))?;
+            for message in &self.snapshot.messages {
+                seq.serialize_element(&SavedMessageSerializer { message, registry: self.registry })?;
+            }
+            seq.end()
+        }
+    }
+
+    impl SerializeWithRegistry for PoolSnapshot {
+        fn serialize<S: Serializer>(&self, serializer: S, registry: &TypeRegistry) -> Result<S::Ok, S::Error> {
+            PoolSnapshotSerializer::new(self, registry).serialize(serializer)
+        }
+    }
+
+    impl<'de> DeserializeWithRegistry<'de> for PoolSnapshot {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D, registry: &TypeRegistry) -> Result<Self, D::Error> {
+            PoolSnapshotDeserializer::new(registry).deserialize(deserializer)
+        }
+    }
+
+    struct SavedMessageSerializer<'a> {
+        message: &'a SavedMessage,
+        registry: &'a TypeRegistry,
+    }
+
+    impl Serialize for SavedMessageSerializer<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("SavedMessage", FIELDS.len())?;
+            state.serialize_field("message", &ReflectSerializer::new(&*self.message.message, self.registry))?;
+            state.serialize_field("remaining", &self.message.remaining)?;
+            state.serialize_field("priority", &self.message.priority)?;
+            state.serialize_field("key", &self.message.key)?;
+            state.end()
+        }
+    }
+
+    /// Deserializes a [`PoolSnapshot`] written by [`PoolSnapshotSerializer`].
+    ///
+    /// Message types have to be registered in `registry`.
+    pub struct PoolSnapshotDeserializer<'a> {
+        registry: &'a TypeRegistry,
+    }
+
+    impl<'a> PoolSnapshotDeserializer<'a> {
+        pub fn new(registry: &'a TypeRegistry) -> Self {
+            Self { registry }
+        }
+    }
+
+    impl<'de> DeserializeSeed<'de> for PoolSnapshotDeserializer<'_> {
+        type Value = PoolSnapshot;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<PoolSnapshot, D::Error> {
+            deserializer.deserialize_seq(self)
+        }
+    }
+
+    impl<'de> Visitor<'de> for PoolSnapshotDeserializer<'_> {
+        type Value = PoolSnapshot;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a sequence of saved messages")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<PoolSnapshot, A::Error> {
+            let mut messages = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some(message) = seq.next_element_seed(SavedMessageDeserializer { registry: self.registry })? {
+                messages.push(message);
+            }
+            Ok(PoolSnapshot { messages })
+        }
+    }
+
+    struct SavedMessageDeserializer<'a> {
+        registry: &'a TypeRegistry,
+    }
+
+    impl<'de> DeserializeSeed<'de> for SavedMessageDeserializer<'_> {
+        type Value = SavedMessage;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<SavedMessage, D::Error> {
+            deserializer.deserialize_struct("SavedMessage", FIELDS, self)
+        }
+    }
+
+    impl<'de> Visitor<'de> for SavedMessageDeserializer<'_> {
+        type Value = SavedMessage;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a saved message")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<SavedMessage, A::Error> {
+            let missing = |index| Error::invalid_length(index, &self);
+            Ok(SavedMessage {
+                message: seq.next_element_seed(ReflectDeserializer::new(self.registry))?.ok_or_else(|| missing(0))?,
+                remaining: seq.next_element()?.ok_or_else(|| missing(1))?,
+                priority: seq.next_element()?.ok_or_else(|| missing(2))?,
+                key: seq.next_element()?.ok_or_else(|| missing(3))?,
+            })
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<SavedMessage, A::Error> {
+            let mut message = None;
+            let mut remaining = None;
+            let mut priority = None;
+            let mut key = None;
+            while let Some(field) = map.next_key::<String>()? {
+                match field.as_str() {
+                    "message" => message = Some(map.next_value_seed(ReflectDeserializer::new(self.registry))?),
+                    "remaining" => remaining = Some(map.next_value()?),
+                    "priority" => priority = Some(map.next_value()?),
+                    "key" => key = Some(map.next_value()?),
+                    _ => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+            }
+            Ok(SavedMessage {
+                message: message.ok_or_else(|| Error::missing_field("message"))?,
+                remaining: remaining.ok_or_else(|| Error::missing_field("remaining"))?,
+                priority: priority.unwrap_or_default(),
+                key: key.unwrap_or_default(),
+            })
+        }
+    }
+}
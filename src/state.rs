@@ -0,0 +1,50 @@
+//! Pools emptied when the app leaves a state.
+
+use bevy::prelude::*;
+use bevy::state::state::StateTransitionEvent;
+
+use crate::{MessagePool, write_all};
+
+/// Drops the pending messages of the [`MessagePool`] on the same entity when the app exits the state.
+///
+/// The pool itself stays, unlike with [`DespawnOnExit`]. Requires
+/// [`WriteAfterAppExt::enable_state_scoped_pools`](crate::WriteAfterAppExt::enable_state_scoped_pools).
+#[derive(Component, Clone, Debug)]
+pub struct ClearPoolOnExit<S: States>(pub S);
+
+/// Writes every pending message of the [`MessagePool`] on the same entity right away when the app
+/// exits the state, in the order they would have become due.
+///
+/// Requires [`WriteAfterAppExt::enable_state_scoped_pools`](crate::WriteAfterAppExt::enable_state_scoped_pools).
+#[derive(Component, Clone, Debug)]
+pub struct FlushPoolOnExit<S: States>(pub S);
+
+type FlushedPool<S> = (Entity, &'static mut MessagePool, &'static FlushPoolOnExit<S>);
+
+pub(crate) fn empty_pools_on_exit_state<S: States>(
+    mut commands: Commands,
+    mut transitions: MessageReader<StateTransitionEvent<S>>,
+    cleared: Query<(&mut MessagePool, &ClearPoolOnExit<S>)>,
+    flushed: Query<FlushedPool<S>, Without<ClearPoolOnExit<S>>>,
+) {
+    let Some(transition) = transitions.read().last() else {
+        return;
+    };
+    if transition.entered == transition.exited {
+        return;
+    }
+    let Some(exited) = &transition.exited else {
+        return;
+    };
+
+    for (mut pool, scope) in cleared {
+        if scope.0 == *exited {
+            pool.clear();
+        }
+    }
+    for (entity, mut pool, scope) in flushed {
+        if scope.0 == *exited {
+            write_all(&mut commands, entity, pool.take_due());
+        }
+    }
+}
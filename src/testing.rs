@@ -0,0 +1,89 @@
+//! Helpers driving the pools from tests without touching [`Time`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use crate::MessagePool;
+
+/// Extension trait for [`App`] and [`World`] to step pools in tests.
+///
+/// Each update runs the `Main` schedule like [`App::update`], without sub apps.
+///
+/// Works with and without `TimePlugin`. With it, long advances are split into steps of the
+/// `max_delta` of `Time<Virtual>` so no time is clamped away, and `FixedUpdate` runs as usual.
+pub trait WriteAfterTestExt {
+    /// Runs updates until `delta` of virtual time has passed, at least one.
+    fn advance_write_after(&mut self, delta: Duration) -> &mut Self;
+
+    /// Runs updates, each advancing time to the next pending message of any [`MessagePool`],
+    /// until every pool is empty. Returns `false` if pools still held messages after `max_steps`.
+    fn run_until_pool_empty(&mut self, max_steps: usize) -> bool;
+}
+
+impl WriteAfterTestExt for App {
+    fn advance_write_after(&mut self, delta: Duration) -> &mut Self {
+        self.world_mut().advance_write_after(delta);
+        self
+    }
+
+    fn run_until_pool_empty(&mut self, max_steps: usize) -> bool {
+        self.world_mut().run_until_pool_empty(max_steps)
+    }
+}
+
+impl WriteAfterTestExt for World {
+    fn advance_write_after(&mut self, delta: Duration) -> &mut Self {
+        advance(self, delta);
+        self
+    }
+
+    fn run_until_pool_empty(&mut self, max_steps: usize) -> bool {
+        let mut pools = self.query::<&MessagePool>();
+        for _ in 0..max_steps {
+            if pools.iter(self).all(MessagePool::is_empty) {
+                return true;
+            }
+            let next = pools.iter(self).filter_map(MessagePool::time_until_next).min().unwrap_or_default();
+            advance(self, next);
+        }
+        pools.iter(self).all(MessagePool::is_empty)
+    }
+}
+
+fn update(world: &mut World) {
+    world.run_schedule(Main);
+    world.clear_trackers();
+}
+
+fn advance(world: &mut World, delta: Duration) {
+    if !world.contains_resource::<TimeUpdateStrategy>() {
+        world.get_resource_or_init::<Time>().advance_by(delta);
+        update(world);
+        // The manual delta would otherwise apply again on the next update.
+        world.resource_mut::<Time>().advance_by(Duration::ZERO);
+        return;
+    }
+
+    // The first update of `Time<Real>` only starts the clock.
+    let mut left = delta;
+    if world.get_resource::<Time<Real>>().is_some_and(|time| time.first_update().is_none()) {
+        update_by(world, Duration::ZERO);
+    }
+    let max_delta = world.get_resource::<Time<Virtual>>().map_or(delta, |time| time.max_delta());
+    loop {
+        let step = left.min(max_delta);
+        left -= step;
+        update_by(world, step);
+        if left.is_zero() {
+            return;
+        }
+    }
+}
+
+fn update_by(world: &mut World, step: Duration) {
+    let previous = std::mem::replace(&mut *world.resource_mut::<TimeUpdateStrategy>(), TimeUpdateStrategy::ManualDuration(step));
+    update(world);
+    *world.resource_mut::<TimeUpdateStrategy>() = previous;
+}
@@ -0,0 +1,248 @@
+//! Timelines of reflected messages authored in RON files, loaded as assets and played into pools.
+
+use std::fmt;
+use std::time::Duration;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy::reflect::TypeRegistry;
+use bevy::reflect::serde::ReflectDeserializer;
+use ron::error::SpannedError;
+use serde::Deserializer;
+use serde::de::{DeserializeSeed, Error, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+use crate::{MarkedMessagePool, MessageHandle, MessagePool, ProcessingSchedule, ReflectDelayedMessage, WriteAfterSet};
+
+/// Loads `.timeline.ron` files as [`TimelineAsset`]s and plays them into the unmarked pools,
+/// with the `timeline` feature.
+///
+/// Timelines changed while playing, like when edited on disk with asset hot reloading, are
+/// synced with their pools: the messages still pending are replaced by the entries of the new
+/// version that are still ahead, so iterating on a sequence doesn't need a restart.
+///
+/// Timelines are played right before the pools are processed in
+/// [`WriteAfterPlugin::schedule`](crate::WriteAfterPlugin::schedule).
+///
+/// Requires `AssetPlugin` and [`WriteAfterPlugin`](crate::WriteAfterPlugin), added before it.
+#[derive(Default)]
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = ProcessingSchedule::of(app);
+        app
+            .init_asset::<TimelineAsset>()
+            .init_asset_loader::<TimelineAssetLoader>()
+            .add_systems(schedule, play_timelines.before(WriteAfterSet));
+    }
+}
+
+/// Messages written at offsets from the moment the timeline is played, see [`MessagePool::play`].
+///
+/// Each entry has its offset in seconds and the message keyed by its type path, like the
+/// components of a scene. Message types need [`ReflectDelayedMessage`] registered:
+///
+/// ```ron
+/// [
+///     (offset: 0.5, message: { "my_game::Dialogue": ("Who's there?") }),
+///     (offset: 2.0, message: { "my_game::Spawn": (count: 3) }),
+/// ]
+/// ```
+#[derive(Asset, TypePath, Debug, Default)]
+pub struct TimelineAsset {
+    pub entries: Vec<TimelineEntry>,
+}
+
+/// An entry of a [`TimelineAsset`].
+#[derive(Debug)]
+pub struct TimelineEntry {
+    /// Time from the moment the timeline is played until the message is written.
+    pub offset: Duration,
+    pub message: Box<dyn PartialReflect>,
+}
+
+impl TimelineAsset {
+    /// Reads a timeline written in RON, with the message types registered in `registry`.
+    pub fn from_ron(ron: &str, registry: &TypeRegistry) -> Result<Self, SpannedError> {
+        let mut deserializer = ron::de::Deserializer::from_str(ron)?;
+        let timeline = TimelineDeserializer { registry }
+            .deserialize(&mut deserializer)
+            .and_then(|timeline| deserializer.end().map(|()| timeline));
+        timeline.map_err(|error| deserializer.span_error(error))
+    }
+}
+
+impl<T: 'static> MarkedMessagePool<T> {
+    /// Schedules every entry of `timeline` after its offset, once the timeline is loaded.
+    ///
+    /// Requires [`TimelinePlugin`], which only plays into unmarked pools.
+    pub fn play(&mut self, timeline: Handle<TimelineAsset>) {
+        self.timelines.push(PlayingTimeline {
+            handle: timeline,
+            started: None,
+            scheduled: Vec::new(),
+        });
+    }
+}
+
+/// A timeline played into a pool, until its last message was written.
+pub(crate) struct PlayingTimeline {
+    handle: Handle<TimelineAsset>,
+    /// Elapsed time of the pool when the timeline started, `None` while it is loading.
+    started: Option<Duration>,
+    scheduled: Vec<MessageHandle>,
+}
+
+fn play_timelines(
+    mut events: MessageReader<AssetEvent<TimelineAsset>>,
+    timelines: Res<Assets<TimelineAsset>>,
+    asset_server: Res<AssetServer>,
+    registry: Res<AppTypeRegistry>,
+    mut pools: Query<&mut MessagePool>,
+) {
+    let modified: HashSet<_> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    for mut pool in &mut pools {
+        if pool.timelines.is_empty() {
+            continue;
+        }
+        let registry = registry.read();
+        let mut playing = std::mem::take(&mut pool.timelines);
+        playing.retain_mut(|playing| {
+            let Some(timeline) = timelines.get(&playing.handle) else {
+                if asset_server.load_state(&playing.handle).is_failed() {
+                    warn!("can't play timeline {:?}, it failed to load", playing.handle.path());
+                    return false;
+                }
+                return playing.started.is_none();
+            };
+            match playing.started {
+                None => playing.started = Some(pool.elapsed),
+                Some(_) if modified.contains(&playing.handle.id()) => {
+                    for &handle in &playing.scheduled {
+                        pool.cancel(handle);
+                    }
+                }
+                Some(_) => {
+                    playing.scheduled.retain(|&handle| pool.messages.contains(handle));
+                    return !playing.scheduled.is_empty();
+                }
+            }
+            // Entries up to the elapsed time of the pool were written already.
+            let position = pool.elapsed - playing.started.unwrap_or_default();
+            playing.scheduled.clear();
+            for entry in timeline.entries.iter().filter(|entry| entry.offset > position) {
+                let Some(message) = ReflectDelayedMessage::schedule(&*entry.message, entry.offset - position, &registry) else {
+                    warn!("can't play message {}, ReflectDelayedMessage isn't registered", entry.message.reflect_type_path());
+                    continue;
+                };
+                playing.scheduled.push(pool.push(message));
+            }
+            true
+        });
+        pool.timelines = playing;
+    }
+}
+
+#[derive(TypePath)]
+struct TimelineAssetLoader {
+    registry: AppTypeRegistry,
+}
+
+impl FromWorld for TimelineAssetLoader {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            registry: world.resource::<AppTypeRegistry>().clone(),
+        }
+    }
+}
+
+impl AssetLoader for TimelineAssetLoader {
+    type Asset = TimelineAsset;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(&self, reader: &mut dyn Reader, _: &(), _: &mut LoadContext<'_>) -> Result<TimelineAsset, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(TimelineAsset::from_ron(std::str::from_utf8(&bytes)?, &self.registry.read())?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["timeline.ron"]
+    }
+}
+
+struct TimelineDeserializer<'a> {
+    registry: &'a TypeRegistry,
+}
+
+impl<'de> DeserializeSeed<'de> for TimelineDeserializer<'_> {
+    type Value = TimelineAsset;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<TimelineAsset, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for TimelineDeserializer<'_> {
+    type Value = TimelineAsset;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of timeline entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TimelineAsset, A::Error> {
+        let mut entries = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(entry) = seq.next_element_seed(TimelineEntryDeserializer { registry: self.registry })? {
+            entries.push(entry);
+        }
+        Ok(TimelineAsset { entries })
+    }
+}
+
+struct TimelineEntryDeserializer<'a> {
+    registry: &'a TypeRegistry,
+}
+
+impl<'de> DeserializeSeed<'de> for TimelineEntryDeserializer<'_> {
+    type Value = TimelineEntry;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<TimelineEntry, D::Error> {
+        deserializer.deserialize_struct("TimelineEntry", &["offset", "message"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for TimelineEntryDeserializer<'_> {
+    type Value = TimelineEntry;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a timeline entry")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<TimelineEntry, A::Error> {
+        let mut offset = None;
+        let mut message = None;
+        while let Some(field) = map.next_key::<String>()? {
+            match field.as_str() {
+                "offset" => offset = Some(map.next_value::<f64>()?),
+                "message" => message = Some(map.next_value_seed(ReflectDeserializer::new(self.registry))?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let offset = offset.ok_or_else(|| Error::missing_field("offset"))?;
+        Ok(TimelineEntry {
+            offset: Duration::try_from_secs_f64(offset).map_err(Error::custom)?,
+            message: message.ok_or_else(|| Error::missing_field("message"))?,
+        })
+    }
+}
@@ -0,0 +1,208 @@
+//! Pools holding messages of a single type, without boxing each scheduled message.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{GlobalMessagePool, IntoDelay, PoolClock, PoolScale, ProcessingSchedule, WriteAfterSet, WriteAfterTimeScale, pools_running};
+
+/// Registers the processing of every [`TypedMessagePool<M>`], and gives the [`GlobalMessagePool`]
+/// one for [`DelayedMessageWriter<M>`](crate::DelayedMessageWriter).
+///
+/// Pools on the frame based clocks are processed in [`WriteAfterPlugin::schedule`](crate::WriteAfterPlugin::schedule)
+/// if it was added before, in `Update` otherwise.
+pub struct TypedMessagePoolPlugin<M>(PhantomData<M>);
+
+impl<M> Default for TypedMessagePoolPlugin<M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<M: Message> Plugin for TypedMessagePoolPlugin<M> {
+    fn build(&self, app: &mut App) {
+        let schedule = ProcessingSchedule::of(app);
+        app
+            .init_resource::<WriteAfterTimeScale>()
+            .register_required_components::<GlobalMessagePool, TypedMessagePool<M>>()
+            .add_systems(schedule, process_typed_messages::<M>.run_if(pools_running::<()>).in_set(WriteAfterSet))
+            .add_systems(FixedUpdate, process_fixed_typed_messages::<M>.run_if(pools_running::<()>).in_set(WriteAfterSet));
+    }
+}
+
+/// Pool of delayed messages of type `M`.
+///
+/// Messages are stored by value in a deadline-ordered queue and written straight into
+/// [`Messages<M>`], so scheduling only allocates when the queue grows. Use it for high-frequency
+/// messages like projectile hits, [`MessagePool`](crate::MessagePool) for everything else.
+///
+/// Requires [`TypedMessagePoolPlugin<M>`]. Honors [`PoolClock`], [`PausedPool`](crate::PausedPool),
+/// [`PoolTimeScale`](crate::PoolTimeScale), [`WriteAfterPlugin::pause_in_state`](crate::WriteAfterPlugin::pause_in_state)
+/// and [`WriteAfterTimeScale`] like other pools.
+#[derive(Component)]
+pub struct TypedMessagePool<M: Message> {
+    queue: BinaryHeap<TypedEntry<M>>,
+    elapsed: Duration,
+    next_order: u64,
+}
+
+impl<M: Message> Default for TypedMessagePool<M> {
+    fn default() -> Self {
+        Self {
+            queue: BinaryHeap::new(),
+            elapsed: Duration::ZERO,
+            next_order: 0,
+        }
+    }
+}
+
+/// Handle to a message scheduled in a [`TypedMessagePool<M>`].
+///
+/// Handles are unique within the pool that returned them. They are a separate type from
+/// [`MessageHandle`](crate::MessageHandle), so they can't be mixed up with the handles of the
+/// [`MessagePool`](crate::MessagePool) on the same entity.
+pub struct TypedMessageHandle<M> {
+    order: u64,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<M> Clone for TypedMessageHandle<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for TypedMessageHandle<M> {}
+
+impl<M> PartialEq for TypedMessageHandle<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.order == other.order
+    }
+}
+
+impl<M> Eq for TypedMessageHandle<M> {}
+
+impl<M> Hash for TypedMessageHandle<M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.order.hash(state);
+    }
+}
+
+impl<M> fmt::Debug for TypedMessageHandle<M> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_tuple("TypedMessageHandle").field(&self.order).finish()
+    }
+}
+
+impl<M: Message> TypedMessagePool<M> {
+    pub fn write_after(&mut self, message: M, delay: impl IntoDelay) -> TypedMessageHandle<M> {
+        let order = self.next_order;
+        self.next_order += 1;
+        self.queue.push(TypedEntry {
+            deadline: self.elapsed + delay.into_delay(),
+            order,
+            message,
+        });
+        TypedMessageHandle { order, marker: PhantomData }
+    }
+
+    /// Drops a scheduled message without writing it, in time linear in the number of pending messages.
+    ///
+    /// Returns `false` if the message was already written or cancelled.
+    pub fn cancel(&mut self, handle: TypedMessageHandle<M>) -> bool {
+        let len = self.queue.len();
+        self.queue.retain(|entry| entry.order != handle.order);
+        self.queue.len() != len
+    }
+
+    /// Number of messages not written yet.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Drops every scheduled message without writing it.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    fn tick(&mut self, delta: Duration, messages: &mut Messages<M>) {
+        self.elapsed += delta;
+        while self.queue.peek().is_some_and(|entry| entry.deadline <= self.elapsed) {
+            messages.write(self.queue.pop().unwrap().message);
+        }
+    }
+}
+
+struct TypedEntry<M> {
+    deadline: Duration,
+    order: u64,
+    message: M,
+}
+
+impl<M> TypedEntry<M> {
+    fn key(&self) -> (Duration, u64) {
+        (self.deadline, self.order)
+    }
+}
+
+impl<M> PartialEq for TypedEntry<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<M> Eq for TypedEntry<M> {}
+
+impl<M> PartialOrd for TypedEntry<M> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<M> Ord for TypedEntry<M> {
+    // Reversed, so the max-heap yields the earliest deadline first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key().cmp(&self.key())
+    }
+}
+
+fn process_typed_messages<M: Message>(
+    time: Res<Time>,
+    real_time: Option<Res<Time<Real>>>,
+    scale: PoolScale,
+    mut messages: ResMut<Messages<M>>,
+    query: Query<(Entity, &mut TypedMessagePool<M>, Option<&PoolClock>)>,
+) {
+    for (entity, mut pool, clock) in query {
+        let delta = match clock.copied().unwrap_or_default() {
+            PoolClock::Virtual => time.delta(),
+            PoolClock::Real => real_time.as_ref().map(|time| time.delta()).unwrap_or_default(),
+            PoolClock::Fixed => continue,
+        };
+        if !pool.is_empty() && let Some(delta) = scale.scale(entity, delta) {
+            pool.tick(delta, &mut messages);
+        }
+    }
+}
+
+fn process_fixed_typed_messages<M: Message>(
+    time: Res<Time>,
+    scale: PoolScale,
+    mut messages: ResMut<Messages<M>>,
+    query: Query<(Entity, &mut TypedMessagePool<M>, &PoolClock)>,
+) {
+    for (entity, mut pool, clock) in query {
+        if *clock == PoolClock::Fixed && !pool.is_empty() && let Some(delta) = scale.scale(entity, time.delta()) {
+            pool.tick(delta, &mut messages);
+        }
+    }
+}